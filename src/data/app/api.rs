@@ -0,0 +1,300 @@
+//! ## Local HTTP API
+//!
+//! Lets monitoring see what the app is doing and
+//! nudge it, without a remote desktop session:
+//! - `GET /status`: state, chosen interface and network,
+//!   latest pings and networks left out of selection
+//! - `GET /networks`: the last scan, merged with the config
+//! - `POST /switch`: switch to the next network,
+//!   or to `{"ssid": "..."}`
+//! - `POST /pause`, `POST /resume`: the autopilot
+//!
+//! Only listens on `127.0.0.1`, and every request
+//! needs `Authorization: Bearer <api.token>`.
+
+use crate::app::{self, commands, control, pinger::Probe};
+use crate::app::wlan::{event, interface, network};
+use crate::app::wlan::network::event::waiter;
+use crate::win::guid;
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use axum::{Router, Json};
+use axum::extract::rejection::JsonRejection;
+use axum::http::{Request, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use serde_derive::{Serialize, Deserialize};
+use serde_json::json;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use once_cell::sync::Lazy;
+use log::{debug, info, warn, error};
+
+
+pub static HANDLE: Lazy<Arc<RwLock<Option<JoinHandle<()>>>>> = Lazy::new(
+    || Arc::new(RwLock::new(None))
+);
+
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InterfaceInfo {
+    guid: String,
+    description: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Excluded {
+    ssid: String,
+    reason: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    state: &'static str,
+    dead_reason: Option<app::DeadReason>,
+    paused: bool,
+    interface: Option<InterfaceInfo>,
+    ssid: Option<String>,
+    probes: Vec<Probe>,
+    /// ## Visible configured networks that won't be chosen
+    excluded: Vec<Excluded>,
+    /// ## Authentication failures in a row, by SSID
+    security_failures: HashMap<String, u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkInfo {
+    ssid: String,
+    visible: bool,
+    configured: bool,
+    chosen: bool,
+    signal_quality: Option<u32>,
+    auth: Option<String>,
+    cipher: Option<String>,
+    generation: Option<String>,
+    excluded: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwitchRequest {
+    ssid: Option<String>,
+}
+
+
+fn error(status: StatusCode, message: impl ToString) -> Response {
+    (status, Json(json!({ "error": message.to_string() }))).into_response()
+}
+
+/// ## Compare without leaking how much of the token matched
+fn same_token(given: &[u8], expected: &[u8]) -> bool {
+    if given.len() != expected.len() {
+        return false
+    }
+
+    given.iter().zip(expected.iter()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn authorize<B>(request: Request<B>, next: Next<B>) -> Response {
+    let config = crate::config();
+    let Some(token) = config.api.token.as_ref() else {
+        return error(StatusCode::UNAUTHORIZED, "api.token is not set")
+    };
+
+    let given = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match given {
+        Some(given) if same_token(given.as_bytes(), token.expose().as_bytes()) => next.run(request).await,
+        _ => error(StatusCode::UNAUTHORIZED, "missing or wrong token"),
+    }
+}
+
+async fn status() -> Response {
+    let state = app::STATE.read().await;
+    let iface = interface::CHOSEN.read().await.get_interface().await;
+    let chosen = network::CHOSEN.read().await;
+    let list = network::LIST.read().await;
+
+    let status = Status {
+        state: if state.is_alive() { "alive" } else { "dead" },
+        dead_reason: state.get_dead_reason().cloned(),
+        paused: control::is_paused(),
+        interface: iface.map(|iface| InterfaceInfo {
+            guid: guid::to_string(&iface.guid),
+            description: iface.description,
+        }),
+        ssid: chosen.get().map(|ssid| ssid.to_string()),
+        probes: Probe::latest(),
+        excluded: list.excluded().into_iter()
+            .map(|(ssid, reason)| Excluded { ssid, reason })
+            .collect(),
+        security_failures: chosen.security_failures().clone(),
+    };
+
+    Json(status).into_response()
+}
+
+async fn networks() -> Response {
+    let config = crate::config();
+    let chosen = network::CHOSEN.read().await;
+    let list = network::LIST.read().await;
+
+    let mut networks = commands::dedup_by_ssid(list.as_slice().to_vec()).into_iter()
+        .map(|live_net| {
+            let cfg_net = config.wifi.networks.iter().find(|net| net.ssid == live_net.ssid);
+
+            NetworkInfo {
+                visible: true,
+                configured: cfg_net.is_some(),
+                chosen: chosen.is_ssid_chosen(&live_net.ssid),
+                signal_quality: Some(live_net.signal_quality),
+                auth: Some(format!("{:?}", live_net.security.auth)),
+                cipher: Some(format!("{:?}", live_net.security.cipher)),
+                generation: live_net.best_generation().map(|gen| format!("{:?}", gen)),
+                excluded: cfg_net.and_then(|cfg_net| network::list::Operator::exclusion(cfg_net, &live_net)),
+                ssid: live_net.ssid,
+            }
+        })
+        .collect::<Vec<NetworkInfo>>();
+
+    for cfg_net in config.wifi.networks.iter() {
+        if networks.iter().any(|net| net.ssid == cfg_net.ssid) {
+            continue
+        }
+
+        networks.push(NetworkInfo {
+            ssid: cfg_net.ssid.clone(),
+            visible: false,
+            configured: true,
+            chosen: chosen.is_ssid_chosen(&cfg_net.ssid),
+            signal_quality: None,
+            auth: None,
+            cipher: None,
+            generation: None,
+            excluded: None,
+        });
+    }
+
+    Json(networks).into_response()
+}
+
+/// ## Go dead the way a failed ping does
+///
+/// Unless something else (i.e. the pinger)
+/// went dead in the meantime and took care of it.
+async fn no_network_left() {
+    if let Err(err) = app::STATE.write().await.dead(app::DeadReason::NoNetwork) {
+        debug!("api: not going dead, the app already is ({:?})", err);
+        return
+    }
+    if !waiter::works().await {
+        waiter::spawn_event_loop().await;
+    }
+}
+
+async fn switch(body: Result<Json<SwitchRequest>, JsonRejection>) -> Response {
+    let ssid = match body {
+        Ok(Json(request)) => request.ssid,
+        Err(JsonRejection::MissingJsonContentType(_)) => None,
+        Err(rejection) => return error(StatusCode::BAD_REQUEST, rejection.body_text()),
+    };
+
+    if let Some(reason) = app::STATE.read().await.get_dead_reason() {
+        return error(StatusCode::CONFLICT, format!("the app is dead: {}", reason.message()))
+    }
+    if let Some(ssid) = ssid.as_ref() {
+        if !crate::config().wifi.networks.iter().any(|net| &net.ssid == ssid) {
+            return error(StatusCode::NOT_FOUND, format!("{} is not in wifi.networks", ssid))
+        }
+    }
+
+    info!("o API: SWITCH requested to {}", ssid.as_deref().unwrap_or("the next network"));
+
+    if let Err(err) = interface::CHOSEN.read().await.scan().await {
+        warn!("! API: could not scan before switching ({:?})", err);
+    }
+    if let Err(err) = network::LIST.write().await.update().await {
+        warn!("! API: could not update the network list ({:?})", err);
+    }
+
+    let mut chosen = network::CHOSEN.write().await;
+
+    let Some(ssid) = ssid else {
+        return match chosen.choose_next(network::SwitchReason::Manual).await.map(|ssid| ssid.to_string()) {
+            Some(ssid) => Json(json!({ "ssid": ssid })).into_response(),
+            None => {
+                std::mem::drop(chosen);
+                no_network_left().await;
+                error(StatusCode::CONFLICT, "could not connect to any network")
+            }
+        }
+    };
+
+    match chosen.choose_ssid(&ssid).await {
+        Ok(true) => {
+            info!("o NETWORK: CHOSE {} (requested through the API)", ssid);
+            return Json(json!({ "ssid": ssid })).into_response()
+        },
+        Ok(false) => warn!("! API: could not connect to {}, choosing another network", ssid),
+        Err(err) => warn!("! API: could not connect to {} ({:?}), choosing another network", ssid, err),
+    }
+
+    match chosen.choose_next(network::SwitchReason::Manual).await.map(|ssid| ssid.to_string()) {
+        Some(other) => error(StatusCode::CONFLICT, format!("could not connect to {}, switched to {} instead", ssid, other)),
+        None => {
+            std::mem::drop(chosen);
+            no_network_left().await;
+            error(StatusCode::CONFLICT, format!("could not connect to {} or any other network", ssid))
+        }
+    }
+}
+
+async fn pause() -> Response {
+    let changed = control::pause();
+    Json(json!({ "paused": true, "changed": changed })).into_response()
+}
+
+async fn resume() -> Response {
+    let changed = control::resume();
+    Json(json!({ "paused": false, "changed": changed })).into_response()
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/networks", get(networks))
+        .route("/switch", post(switch))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .layer(middleware::from_fn(authorize))
+}
+
+pub async fn event_loop() {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, crate::config().api.port));
+
+    let server = match axum::Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(err) => {
+            error!("x API: could not listen on {} ({})", addr, err);
+            return
+        }
+    };
+    info!("o API: LISTENING on http://{}", addr);
+
+    if let Err(err) = server.serve(router().into_make_service()).await {
+        error!("x API: stopped ({})", err);
+    }
+}
+
+event::looping::works!(async fn works(HANDLE));
+event::looping::spawner!(async fn spawn_event_loop(HANDLE, event_loop, works));
+event::looping::closer!(async fn close_event_loop(HANDLE));
@@ -0,0 +1,94 @@
+//! ## Config file formats
+//!
+//! Besides `cfg.json`, the config can be written as
+//! `cfg.toml` or `cfg.yaml`, which allow comments.
+//! The format is picked by the file extension.
+
+use serde::{Serialize, de::DeserializeOwned};
+use std::path::{Path, PathBuf};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+impl Format {
+    /// ## Config file names, in the order they're looked for
+    pub const FILE_NAMES: [&'static str; 4] = ["cfg.json", "cfg.toml", "cfg.yaml", "cfg.yml"];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None
+        }
+    }
+
+    /// ## Format of a path, JSON if the extension is unknown
+    pub fn of(path: &Path) -> Self {
+        Self::from_path(path).unwrap_or(Self::Json)
+    }
+
+    /// ## Parse into plain JSON, i.e. for migrations
+    pub fn parse(&self, bytes: &[u8]) -> Result<serde_json::Value, String> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(|err| err.to_string()),
+            Self::Toml => toml::from_str(utf8(bytes)?).map_err(|err| err.to_string()),
+            Self::Yaml => serde_yaml::from_slice(bytes).map_err(|err| err.to_string()),
+        }
+    }
+
+    /// ## Deserialize, prefixing errors with the path of the bad value
+    pub fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String> {
+        match self {
+            Self::Json => {
+                let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+                serde_path_to_error::deserialize(&mut deserializer)
+                    .map_err(|err| format!("{}: {}", err.path(), err.inner()))
+            },
+            Self::Toml => {
+                let deserializer = toml::Deserializer::new(utf8(bytes)?);
+                serde_path_to_error::deserialize(deserializer)
+                    .map_err(|err| format!("{}: {}", err.path(), err.inner()))
+            },
+            Self::Yaml => {
+                let deserializer = serde_yaml::Deserializer::from_slice(bytes);
+                serde_path_to_error::deserialize(deserializer)
+                    .map_err(|err| format!("{}: {}", err.path(), err.inner()))
+            },
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec_pretty(value).map_err(|err| err.to_string()),
+            Self::Toml => toml::to_string_pretty(value).map(String::into_bytes).map_err(|err| err.to_string()),
+            Self::Yaml => serde_yaml::to_string(value).map(String::into_bytes).map_err(|err| err.to_string()),
+        }
+    }
+
+    /// ## Whether the file can have comments
+    pub fn has_comments(&self) -> bool {
+        *self != Self::Json
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str, String> {
+    std::str::from_utf8(bytes).map_err(|err| err.to_string())
+}
+
+/// ## Config file in `dir`
+///
+/// The first one of `Format::FILE_NAMES` that exists,
+/// or `cfg.json` if there's none.
+pub fn find(dir: &Path) -> PathBuf {
+    Format::FILE_NAMES.iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(Format::FILE_NAMES[0]))
+}
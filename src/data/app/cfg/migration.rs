@@ -0,0 +1,199 @@
+//! ## Config schema migrations
+//!
+//! `cfg.json` carries a `schemaVersion`. Files written
+//! before it existed are version `1`. An older file is
+//! upgraded step by step, as plain JSON, before it's
+//! deserialized into `Config`.
+//!
+//! Migrations work on `serde_json::Value` and not on
+//! `Config`, because secrets are redacted when a
+//! `Config` is serialized.
+
+use serde_json::{Map, Value, json};
+use std::fmt;
+
+
+pub const CURRENT: u32 = 2;
+/// ## Version of files without `schemaVersion`
+pub const UNVERSIONED: u32 = 1;
+
+/// ## Upgrades a config from version `N` to `N + 1`
+type Step = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// ## Steps, by the version they upgrade from
+const STEPS: &[(u32, Step)] = &[
+    (1, ping_domains_to_targets),
+];
+
+#[derive(Debug)]
+pub enum Error {
+    NotAnObject,
+    BadVersion(Value),
+    /// ## File was written by a newer version of the app
+    TooNew(u32),
+    Step { from: u32, reason: String },
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnObject => write!(f, "the config is not a JSON object"),
+            Self::BadVersion(version) => write!(f, "schemaVersion {} is not a positive integer", version),
+            Self::TooNew(version) => write!(
+                f, "schemaVersion {} is newer than the supported {}, update the app", version, CURRENT
+            ),
+            Self::Step { from, reason } => write!(
+                f, "could not migrate from schemaVersion {} to {}: {}", from, from + 1, reason
+            ),
+        }
+    }
+}
+
+pub fn version(config: &Value) -> Result<u32, Error> {
+    let Some(object) = config.as_object() else {
+        return Err(Error::NotAnObject)
+    };
+
+    match object.get("schemaVersion") {
+        None => Ok(UNVERSIONED),
+        Some(version) => version.as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= UNVERSIONED)
+            .ok_or_else(|| Error::BadVersion(version.clone()))
+    }
+}
+
+/// ## Bring a config up to `CURRENT`
+///
+/// Returns the version it was at, or `None`
+/// if it's already current.
+pub fn migrate(config: &mut Value) -> Result<Option<u32>, Error> {
+    let from = version(config)?;
+
+    if from > CURRENT {
+        return Err(Error::TooNew(from))
+    }
+    if from == CURRENT {
+        return Ok(None)
+    }
+
+    let object = config.as_object_mut().unwrap();
+
+    for (step_from, step) in STEPS.iter().filter(|(step_from, _)| *step_from >= from) {
+        step(object).map_err(|reason| Error::Step { from: *step_from, reason })?;
+        object.insert("schemaVersion".to_string(), json!(step_from + 1));
+    }
+
+    // keep the version on top, where it's easy to see
+    let mut versioned = Map::new();
+    versioned.insert("schemaVersion".to_string(), json!(CURRENT));
+    versioned.extend(std::mem::take(object).into_iter().filter(|(key, _)| key != "schemaVersion"));
+    *object = versioned;
+
+    Ok(Some(from))
+}
+
+/// ## 1 -> 2: `ping.domains` became typed `ping.targets`
+///
+/// `{"list": ["google.com"], "mode": "allIpsFromEach"}` turns into
+/// `[{"type": "domain", "name": "google.com", "mode": "allIpsFromEach"}]`.
+fn ping_domains_to_targets(config: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(ping) = config.get_mut("ping") else {
+        return Ok(())
+    };
+    let Some(ping) = ping.as_object_mut() else {
+        return Err("ping is not an object".to_string())
+    };
+    let Some(domains) = ping.remove("domains") else {
+        return Ok(())
+    };
+
+    let mode = domains.get("mode").cloned().unwrap_or(json!("firstIpFromEach"));
+    let Some(list) = domains.get("list").and_then(|list| list.as_array()) else {
+        return Err("ping.domains.list is not an array".to_string())
+    };
+
+    let targets = list.iter().map(|name| json!({
+        "type": "domain",
+        "name": name,
+        "mode": mode
+    })).collect::<Vec<Value>>();
+
+    let mut migrated = Map::new();
+    migrated.insert("targets".to_string(), Value::Array(targets));
+    migrated.extend(std::mem::take(ping));
+    *ping = migrated;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_becomes_current() {
+        let mut config = json!({
+            "ping": {
+                "domains": { "list": ["google.com", "1.1.1.1.nip.io"], "mode": "allIpsFromEach" },
+                "timeoutMs": 1000
+            },
+            "wifi": { "networks": [] }
+        });
+
+        assert_eq!(migrate(&mut config).unwrap(), Some(1));
+        assert_eq!(config, json!({
+            "schemaVersion": CURRENT,
+            "ping": {
+                "targets": [
+                    { "type": "domain", "name": "google.com", "mode": "allIpsFromEach" },
+                    { "type": "domain", "name": "1.1.1.1.nip.io", "mode": "allIpsFromEach" }
+                ],
+                "timeoutMs": 1000
+            },
+            "wifi": { "networks": [] }
+        }));
+        assert_eq!(config.as_object().unwrap().keys().next().unwrap(), "schemaVersion");
+    }
+
+    #[test]
+    fn v1_without_mode_gets_the_default() {
+        let mut config = json!({ "schemaVersion": 1, "ping": { "domains": { "list": ["google.com"] } } });
+
+        migrate(&mut config).unwrap();
+        assert_eq!(
+            config["ping"]["targets"],
+            json!([{ "type": "domain", "name": "google.com", "mode": "firstIpFromEach" }])
+        );
+    }
+
+    #[test]
+    fn current_is_left_alone() {
+        let original = json!({ "schemaVersion": CURRENT, "ping": { "targets": [] } });
+        let mut config = original.clone();
+
+        assert_eq!(migrate(&mut config).unwrap(), None);
+        assert_eq!(config, original);
+    }
+
+    #[test]
+    fn too_new_is_rejected() {
+        let mut config = json!({ "schemaVersion": CURRENT + 1 });
+
+        assert!(matches!(migrate(&mut config), Err(Error::TooNew(version)) if version == CURRENT + 1));
+    }
+
+    #[test]
+    fn bad_version_is_rejected() {
+        for version in [json!(0), json!(-1), json!(1.5), json!("2"), json!(null)] {
+            let mut config = json!({ "schemaVersion": version });
+            assert!(matches!(migrate(&mut config), Err(Error::BadVersion(_))), "{}", version);
+        }
+    }
+
+    #[test]
+    fn bad_step_input_is_reported() {
+        let mut config = json!({ "ping": { "domains": { "list": "google.com" } } });
+
+        assert!(matches!(migrate(&mut config), Err(Error::Step { from: 1, .. })));
+    }
+}
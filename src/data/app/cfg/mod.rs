@@ -0,0 +1,710 @@
+pub mod validation;
+pub mod migration;
+pub mod format;
+pub use format::Format;
+
+use super::error::RwError;
+use super::secret::{self, Secret};
+use crate::win::wlan::Bssid;
+use crate::win::wlan::network::{Band, phy::Generation, profile};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use serde_derive::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use log::{info, warn};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun
+}
+impl Weekday {
+    pub fn from_chrono(weekday: chrono::Weekday) -> Self {
+        match weekday {
+            chrono::Weekday::Mon => Self::Mon,
+            chrono::Weekday::Tue => Self::Tue,
+            chrono::Weekday::Wed => Self::Wed,
+            chrono::Weekday::Thu => Self::Thu,
+            chrono::Weekday::Fri => Self::Fri,
+            chrono::Weekday::Sat => Self::Sat,
+            chrono::Weekday::Sun => Self::Sun,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleWindow {
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub timezone: Option<String>,
+    pub windows: Vec<ScheduleWindow>,
+}
+
+/// ## Security of a hidden network
+/// 
+/// Visible networks take it from the scan list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum HiddenSecurity {
+    Open,
+    Wpa2Personal,
+    Wpa3Personal,
+}
+impl HiddenSecurity {
+    pub fn to_profile_security(self) -> (profile::raw::Authentication, profile::raw::Encryption) {
+        match self {
+            Self::Open => (profile::raw::Authentication::Open, profile::raw::Encryption::None),
+            Self::Wpa2Personal => (profile::raw::Authentication::WPA2PSK, profile::raw::Encryption::AES),
+            Self::Wpa3Personal => (profile::raw::Authentication::WPA3SAE, profile::raw::Encryption::AES),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Enterprise {
+    pub method: profile::EapMethod,
+    pub identity: Option<String>,
+    pub anonymous_identity: Option<String>,
+    pub server_names: Option<String>,
+    #[serde(default)]
+    pub ca_thumbprints: Vec<String>,
+    pub certificate_thumbprint: Option<String>,
+}
+impl Enterprise {
+    /// ## Thumbprints are normalized the way they're read from profiles
+    pub fn to_profile_enterprise(&self) -> profile::Enterprise {
+        profile::Enterprise {
+            method: self.method,
+            server_names: self.server_names.clone(),
+            ca_thumbprints: self.ca_thumbprints.iter()
+                .map(|thumbprint| profile::onex::thumbprint_from_xml(thumbprint))
+                .collect(),
+            certificate_thumbprint: self.certificate_thumbprint.as_ref()
+                .map(|thumbprint| profile::onex::thumbprint_from_xml(thumbprint)),
+            anonymous_identity: self.anonymous_identity.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Network {
+    pub ssid: String,
+    pub password: Option<Secret>,
+    /// ## Environment variable with the password
+    pub password_env: Option<String>,
+    /// ## File with the password on the first line
+    pub password_file: Option<PathBuf>,
+    /// ## Command that prints the password
+    pub password_command: Option<String>,
+    pub bssid: Option<String>,
+    pub min_generation: Option<Generation>,
+    #[serde(default)]
+    pub hidden: bool,
+    /// ## Security of a hidden network
+    /// WPA2-Personal if there's a password, open otherwise.
+    pub security: Option<HiddenSecurity>,
+    pub schedule: Option<Schedule>,
+    pub enterprise: Option<Enterprise>,
+}
+impl Network {
+    pub fn bssid(&self) -> Option<Bssid> {
+        self.bssid.as_ref().and_then(|bssid| bssid.parse().ok())
+    }
+
+    pub fn password_source(&self) -> Result<Option<secret::Source>, secret::Error> {
+        secret::pick(vec![
+            self.password.clone().map(secret::Source::Literal),
+            self.password_env.clone().map(secret::Source::Env),
+            self.password_file.clone().map(secret::Source::File),
+            self.password_command.clone().map(secret::Source::Command),
+        ])
+    }
+
+    /// ## Get the password from wherever it's set
+    pub async fn password(&self) -> Result<Option<Secret>, secret::Error> {
+        match self.password_source()? {
+            Some(source) => secret::resolve(&source).await.map(Some),
+            None => Ok(None)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WiFiDisconnect {
+    pub timeout_ms: u64,
+}
+impl Default for WiFiDisconnect {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 4000
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WiFiConnect {
+    pub timeout_ms: u64,
+    /// ## Authentication failures in a row before the profile is rewritten
+    /// `0` disables it.
+    #[serde(default = "WiFiConnect::default_reprovision_after_failures")]
+    pub reprovision_after_failures: u32,
+}
+impl WiFiConnect {
+    fn default_reprovision_after_failures() -> u32 {
+        3
+    }
+}
+impl Default for WiFiConnect {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 4000,
+            reprovision_after_failures: Self::default_reprovision_after_failures()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WiFiScan {
+    pub timeout_ms: u64,
+}
+impl Default for WiFiScan {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 3000
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WiFiRoam {
+    pub enabled: bool,
+    pub interval_ms: u64,
+    pub min_quality: u32,
+    pub min_quality_gain: u32,
+}
+impl Default for WiFiRoam {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: 10000,
+            min_quality: 50,
+            min_quality_gain: 15
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WiFiSchedule {
+    pub check_interval_ms: u64,
+}
+impl Default for WiFiSchedule {
+    fn default() -> Self {
+        Self {
+            check_interval_ms: 30000
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WiFiPreference {
+    pub bands: Vec<Band>,
+    pub prefer_newer_generation: bool,
+    pub min_generation: Option<Generation>,
+    pub min_quality: u32,
+}
+impl Default for WiFiPreference {
+    fn default() -> Self {
+        Self {
+            bands: vec![Band::SixGhz, Band::FiveGhz, Band::TwoPointFourGhz],
+            prefer_newer_generation: true,
+            min_generation: None,
+            min_quality: 30
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum WiFiPriority {
+    List,
+    SignalStrength
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WiFi {
+    pub networks: Vec<Network>,
+    pub scan: WiFiScan,
+    pub connect: WiFiConnect,
+    pub disconnect: WiFiDisconnect,
+    #[serde(default)]
+    pub roam: WiFiRoam,
+    #[serde(default)]
+    pub preference: WiFiPreference,
+    #[serde(default)]
+    pub schedule: WiFiSchedule,
+    /// ## Store keys encrypted with DPAPI instead of plaintext
+    #[serde(default)]
+    pub protect_keys: bool,
+}
+impl WiFi {
+    pub fn network_ssids_str(&self) -> Vec<&str> {
+        self.networks.iter().map(|net| net.ssid.as_str()).collect::<Vec<&str>>()
+    }
+
+    pub fn network_ssids_string(&self) -> Vec<String> {
+        self.networks.iter().map(|net| net.ssid.clone()).collect::<Vec<String>>()
+    }
+}
+impl Default for WiFi {
+    fn default() -> Self {
+        Self {
+            networks: vec![],
+            scan: WiFiScan::default(),
+            connect: WiFiConnect::default(),
+            disconnect: WiFiDisconnect::default(),
+            roam: WiFiRoam::default(),
+            preference: WiFiPreference::default(),
+            schedule: WiFiSchedule::default(),
+            protect_keys: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Interfaces {
+    pub priority: Vec<String>,
+}
+impl Default for Interfaces {
+    fn default() -> Self {
+        Self {
+            priority: vec![]
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DomainsMode {
+    FirstIpFromEach,
+    AllIpsFromEach,
+}
+impl Default for DomainsMode {
+    fn default() -> Self {
+        Self::FirstIpFromEach
+    }
+}
+
+/// ## Something to ping
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Target {
+    /// ## Domain, resolved into IPs according to `mode`
+    Domain {
+        name: String,
+        #[serde(default)]
+        mode: DomainsMode
+    },
+    Ip {
+        address: IpAddr
+    },
+}
+impl Target {
+    pub fn domain(name: impl ToString) -> Self {
+        Self::Domain { name: name.to_string(), mode: DomainsMode::default() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Ping {
+    pub targets: Vec<Target>,
+    pub timeout_ms: u32,
+    pub interval_ms: u64,
+    pub max_errors: u32,
+}
+impl Default for Ping {
+    fn default() -> Self {
+        Self {
+            targets: vec![
+                Target::domain("google.com"),
+                Target::domain("amazon.com"),
+                Target::domain("microsoft.com")
+            ],
+            timeout_ms: 1500,
+            interval_ms: 1000,
+            max_errors: 3
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Reload {
+    pub enabled: bool,
+    pub interval_ms: u64,
+}
+impl Default for Reload {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 2000
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Api {
+    pub enabled: bool,
+    pub port: u16,
+    /// ## Token every request has to bring
+    pub token: Option<Secret>,
+}
+impl Default for Api {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8371,
+            token: None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Metrics {
+    pub enabled: bool,
+    /// ## Address and port to serve `/metrics` on
+    pub listen: SocketAddr,
+}
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: SocketAddr::from((Ipv4Addr::LOCALHOST, 9371))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    /// ## Another network was chosen
+    Switch,
+    Dead,
+    Alive,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEndpoint {
+    pub url: String,
+    /// ## Events to send, all of them by default
+    #[serde(default = "WebhookEndpoint::default_events")]
+    pub events: Vec<WebhookEvent>,
+}
+impl WebhookEndpoint {
+    fn default_events() -> Vec<WebhookEvent> {
+        vec![WebhookEvent::Switch, WebhookEvent::Dead, WebhookEvent::Alive]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhooks {
+    pub endpoints: Vec<WebhookEndpoint>,
+    pub timeout_ms: u64,
+    /// ## Attempts per delivery, including the first one
+    pub max_attempts: u32,
+    /// ## Delay before the first retry, doubled after each one
+    pub retry_initial_ms: u64,
+    pub retry_max_ms: u64,
+}
+impl Default for Webhooks {
+    fn default() -> Self {
+        Self {
+            endpoints: vec![],
+            timeout_ms: 5000,
+            max_attempts: 10,
+            retry_initial_ms: 1000,
+            retry_max_ms: 60000
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum OnFailure {
+    /// ## Log it and run the next hook
+    #[default]
+    Continue,
+    /// ## Run it again up to `retries` times
+    Retry,
+    /// ## Log it and skip the remaining hooks of the event
+    Abort,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Hook {
+    /// ## Shell command, run with `cmd /C`
+    pub command: String,
+    #[serde(default = "Hook::default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub on_failure: OnFailure,
+    #[serde(default = "Hook::default_retries")]
+    pub retries: u32,
+}
+impl Hook {
+    fn default_timeout_ms() -> u64 {
+        30000
+    }
+
+    fn default_retries() -> u32 {
+        2
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    NetworkChosen,
+    NetworkUnchosen,
+    InterfaceChosen,
+    Dead,
+    Alive,
+    ProbeOutage,
+}
+impl HookEvent {
+    pub const ALL: [Self; 6] = [
+        Self::NetworkChosen,
+        Self::NetworkUnchosen,
+        Self::InterfaceChosen,
+        Self::Dead,
+        Self::Alive,
+        Self::ProbeOutage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NetworkChosen => "networkChosen",
+            Self::NetworkUnchosen => "networkUnchosen",
+            Self::InterfaceChosen => "interfaceChosen",
+            Self::Dead => "dead",
+            Self::Alive => "alive",
+            Self::ProbeOutage => "probeOutage",
+        }
+    }
+}
+
+/// ## Commands to run on each event, in order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Hooks {
+    #[serde(default)]
+    pub network_chosen: Vec<Hook>,
+    #[serde(default)]
+    pub network_unchosen: Vec<Hook>,
+    #[serde(default)]
+    pub interface_chosen: Vec<Hook>,
+    #[serde(default)]
+    pub dead: Vec<Hook>,
+    #[serde(default)]
+    pub alive: Vec<Hook>,
+    #[serde(default)]
+    pub probe_outage: Vec<Hook>,
+}
+impl Hooks {
+    pub fn of(&self, event: HookEvent) -> &[Hook] {
+        match event {
+            HookEvent::NetworkChosen => &self.network_chosen,
+            HookEvent::NetworkUnchosen => &self.network_unchosen,
+            HookEvent::InterfaceChosen => &self.interface_chosen,
+            HookEvent::Dead => &self.dead,
+            HookEvent::Alive => &self.alive,
+            HookEvent::ProbeOutage => &self.probe_outage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    pub schema_version: u32,
+    pub ping: Ping,
+    pub interfaces: Interfaces,
+    pub wifi: WiFi,
+    #[serde(default)]
+    pub reload: Reload,
+    #[serde(default)]
+    pub api: Api,
+    #[serde(default)]
+    pub metrics: Metrics,
+    #[serde(default)]
+    pub webhooks: Webhooks,
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+impl Config {
+    /// ## Load the file, migrating it in memory
+    ///
+    /// The file itself is left as is,
+    /// see `load_and_upgrade`.
+    pub async fn load() -> Result<Self, RwError> {
+        let (bytes, format, _) = Self::read_migrated().await?;
+        Self::deserialize(format, &bytes)
+    }
+
+    /// ## Load the file, writing it back if it was migrated
+    ///
+    /// Only for the app's startup, so commands
+    /// and reloads never rewrite the user's file.
+    pub async fn load_and_upgrade() -> Result<Self, RwError> {
+        let (bytes, format, migrated_from) = Self::read_migrated().await?;
+
+        if let Some(from) = migrated_from {
+            if crate::app::cli::options().dry_run {
+                info!("o DRY RUN: would upgrade {} from schemaVersion {}", crate::CFG_PATH.display(), from);
+            } else {
+                Self::upgrade_file(from, &bytes).await?;
+
+                if format.has_comments() {
+                    warn!("! CONFIG: comments of the old file were kept only in the backup");
+                }
+            }
+        }
+
+        Self::deserialize(format, &bytes)
+    }
+
+    /// ## Bytes of the file, migrated to the current version
+    ///
+    /// Also returns the version it was migrated from,
+    /// `None` if it was current.
+    async fn read_migrated() -> Result<(Vec<u8>, Format, Option<u32>), RwError> {
+        let bytes = tokio::fs::read(crate::CFG_PATH.as_path()).await;
+        if let Err(err) = bytes {
+            return Err(RwError::ReadError(err.to_string()))
+        }
+        let bytes = bytes.unwrap();
+        let format = Format::of(crate::CFG_PATH.as_path());
+
+        let value = format.parse(&bytes);
+        if let Err(err) = value {
+            return Err(RwError::DeserializeError(err))
+        }
+        let mut value = value.unwrap();
+
+        let migrated_from = match migration::migrate(&mut value) {
+            Ok(migrated_from) => migrated_from,
+            Err(err) => return Err(RwError::MigrateError(err.to_string()))
+        };
+        if migrated_from.is_none() {
+            return Ok((bytes, format, None))
+        }
+
+        match format.serialize(&value) {
+            Ok(bytes) => Ok((bytes, format, migrated_from)),
+            Err(err) => Err(RwError::MigrateError(err))
+        }
+    }
+
+    fn deserialize(format: Format, bytes: &[u8]) -> Result<Self, RwError> {
+        format.deserialize(bytes).map_err(RwError::DeserializeError)
+    }
+
+    /// ## Back up the old file and write the migrated one
+    ///
+    /// The backup is `<file name>.bak-<old version>`,
+    /// i.e. `cfg.json.bak-1`.
+    /// An existing backup of the same version is kept,
+    /// since it's closer to the original.
+    async fn upgrade_file(from: u32, migrated: &[u8]) -> Result<(), RwError> {
+        let mut backup = crate::CFG_PATH.as_os_str().to_owned();
+        backup.push(format!(".bak-{}", from));
+        let backup = PathBuf::from(backup);
+
+        if !backup.exists() {
+            if let Err(err) = tokio::fs::copy(crate::CFG_PATH.as_path(), &backup).await {
+                return Err(RwError::WriteError(format!("backup to {}: {}", backup.display(), err)))
+            }
+        }
+
+        if let Err(err) = tokio::fs::write(crate::CFG_PATH.as_path(), migrated).await {
+            return Err(RwError::WriteError(err.to_string()))
+        }
+
+        info!(
+            "o CONFIG: MIGRATED from schemaVersion {} to {}, the old file is at {}",
+            from, migration::CURRENT, backup.display()
+        );
+
+        Ok(())
+    }
+
+    pub async fn save(&self) -> tokio::io::Result<()> {
+        let bytes = Format::of(crate::CFG_PATH.as_path()).serialize(&self).unwrap();
+        tokio::fs::write(crate::CFG_PATH.as_path(), bytes).await
+    }
+
+    pub async fn default_and_save() -> tokio::io::Result<Self> {
+        let this = Self::default();
+        this.save().await?;
+        Ok(this)
+    }
+
+    /// ## JSON Schema of the config file
+    pub fn json_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(Self)
+    }
+
+    pub fn is_valid(&self) -> Result<(), Vec<validation::Issue>> {
+        let issues = validation::validate(self);
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            schema_version: migration::CURRENT,
+            ping: Ping::default(),
+            interfaces: Interfaces::default(),
+            wifi: WiFi::default(),
+            reload: Reload::default(),
+            api: Api::default(),
+            metrics: Metrics::default(),
+            webhooks: Webhooks::default(),
+            hooks: Hooks::default(),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,432 @@
+//! ## Config validation
+//!
+//! Every problem is an `Issue` with a JSON path
+//! (i.e. `wifi.networks[1].ssid`). When the file
+//! is at hand, paths are turned into line and column
+//! numbers, so the problem is easy to find.
+
+use super::{Config, Format, HookEvent};
+use crate::app::secret;
+use crate::app::wlan::network::schedule;
+use crate::win::wlan::Bssid;
+
+use std::collections::HashMap;
+use std::fmt;
+use log::error;
+
+
+/// ## Timeouts and intervals above this are surely a typo
+pub const MAX_DURATION_MS: u64 = 10 * 60 * 1000;
+pub const MAX_SSID_BYTES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub path: String,
+    pub message: String,
+}
+impl Issue {
+    pub fn new(path: impl ToString, message: impl ToString) -> Self {
+        Self { path: path.to_string(), message: message.to_string() }
+    }
+}
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+fn is_guid(guid: &str) -> bool {
+    let groups = guid.split('-').collect::<Vec<&str>>();
+    let lengths = groups.iter().map(|group| group.len()).collect::<Vec<usize>>();
+
+    lengths == [8, 4, 4, 4, 12]
+    && groups.iter().all(|group| group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn is_passphrase(password: &str) -> bool {
+    (8..=63).contains(&password.len())
+    || (password.len() == 64 && password.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn check_duration(issues: &mut Vec<Issue>, path: &str, ms: u64) {
+    if ms == 0 {
+        issues.push(Issue::new(path, "can't be 0"))
+    } else if ms > MAX_DURATION_MS {
+        issues.push(Issue::new(path, format!("{} ms is too long, the maximum is {}", ms, MAX_DURATION_MS)))
+    }
+}
+
+pub fn validate(config: &Config) -> Vec<Issue> {
+    let mut issues = vec![];
+
+    for (idx, target) in config.ping.targets.iter().enumerate() {
+        let super::Target::Domain { name: domain, .. } = target else {
+            continue
+        };
+        let path = format!("ping.targets[{}].name", idx);
+
+        if domain.contains("://") {
+            issues.push(Issue::new(&path, format!("{:?} has a protocol, put just the domain", domain)))
+        } else if domain.contains(':') {
+            issues.push(Issue::new(&path, format!("{:?} has a port, put just the domain", domain)))
+        } else if domain.contains('/') {
+            issues.push(Issue::new(&path, format!("{:?} has a path, put just the domain", domain)))
+        }
+    }
+    if config.ping.targets.is_empty() {
+        issues.push(Issue::new("ping.targets", "there is nothing to ping"))
+    }
+    check_duration(&mut issues, "ping.timeoutMs", config.ping.timeout_ms as u64);
+    check_duration(&mut issues, "ping.intervalMs", config.ping.interval_ms);
+    if config.ping.max_errors == 0 {
+        issues.push(Issue::new("ping.maxErrors", "can't be 0"))
+    }
+
+    for (idx, guid) in config.interfaces.priority.iter().enumerate() {
+        if !is_guid(guid) {
+            issues.push(Issue::new(
+                format!("interfaces.priority[{}]", idx),
+                format!("{:?} is not a GUID like \"0A1B2C3D-4E5F-6A7B-8C9D-0E1F2A3B4C5D\"", guid)
+            ))
+        }
+    }
+
+    if config.wifi.networks.is_empty() {
+        issues.push(Issue::new("wifi.networks", "there are no networks"))
+    }
+    for (idx, network) in config.wifi.networks.iter().enumerate() {
+        let path = format!("wifi.networks[{}]", idx);
+
+        if network.ssid.is_empty() {
+            issues.push(Issue::new(format!("{}.ssid", path), "can't be empty"))
+        } else if network.ssid.len() > MAX_SSID_BYTES {
+            issues.push(Issue::new(
+                format!("{}.ssid", path),
+                format!("{:?} is {} bytes long, the maximum is {}", network.ssid, network.ssid.len(), MAX_SSID_BYTES)
+            ))
+        }
+
+        let first = config.wifi.networks.iter().position(|net| net.ssid == network.ssid);
+        if let Some(first) = first.filter(|first| *first != idx) {
+            issues.push(Issue::new(
+                format!("{}.ssid", path),
+                format!("{:?} is already at wifi.networks[{}]", network.ssid, first)
+            ))
+        }
+
+        match network.password_source() {
+            Err(secret::Error::Ambiguous(kinds)) => issues.push(Issue::new(
+                &path,
+                format!("only one of {} can be set", kinds.join(", "))
+            )),
+            // EAP passwords have no length rules
+            Ok(Some(secret::Source::Literal(password)))
+                if network.enterprise.is_none() && !is_passphrase(password.expose()) =>
+            {
+                issues.push(Issue::new(
+                    format!("{}.password", path),
+                    format!(
+                        "is {} characters long, WPA passphrases are 8 to 63 characters or 64 hex digits",
+                        password.expose().len()
+                    )
+                ))
+            },
+            _ => ()
+        }
+
+        if let Some(network_schedule) = network.schedule.as_ref() {
+            if let Some(timezone) = network_schedule.timezone.as_ref() {
+                if timezone.parse::<chrono_tz::Tz>().is_err() {
+                    issues.push(Issue::new(
+                        format!("{}.schedule.timezone", path),
+                        format!("{:?} is not an IANA time zone like \"Europe/Berlin\"", timezone)
+                    ))
+                }
+            }
+            for (window_idx, window) in network_schedule.windows.iter().enumerate() {
+                for (field, time) in [("from", &window.from), ("to", &window.to)] {
+                    if schedule::parse_time(time).is_none() {
+                        issues.push(Issue::new(
+                            format!("{}.schedule.windows[{}].{}", path, window_idx, field),
+                            format!("{:?} is not a time like \"22:30\"", time)
+                        ))
+                    }
+                }
+            }
+        }
+
+        if let Some(bssid) = network.bssid.as_ref() {
+            if let Err(err) = bssid.parse::<Bssid>() {
+                issues.push(Issue::new(
+                    format!("{}.bssid", path),
+                    format!("{:?} is not a MAC address like \"A0:B1:C2:D3:E4:F5\": {}", bssid, err)
+                ))
+            }
+        }
+    }
+    check_duration(&mut issues, "wifi.scan.timeoutMs", config.wifi.scan.timeout_ms);
+    check_duration(&mut issues, "wifi.connect.timeoutMs", config.wifi.connect.timeout_ms);
+    check_duration(&mut issues, "wifi.disconnect.timeoutMs", config.wifi.disconnect.timeout_ms);
+    check_duration(&mut issues, "wifi.roam.intervalMs", config.wifi.roam.interval_ms);
+    check_duration(&mut issues, "wifi.schedule.checkIntervalMs", config.wifi.schedule.check_interval_ms);
+    check_duration(&mut issues, "reload.intervalMs", config.reload.interval_ms);
+
+    if config.api.enabled {
+        match config.api.token.as_ref() {
+            None => issues.push(Issue::new("api.token", "is required when the API is enabled")),
+            Some(token) if token.expose().len() < 16 => issues.push(Issue::new(
+                "api.token",
+                "is too short, use at least 16 characters"
+            )),
+            _ => ()
+        }
+        if config.api.port == 0 {
+            issues.push(Issue::new("api.port", "can't be 0"))
+        }
+    }
+
+    for (endpoint_idx, endpoint) in config.webhooks.endpoints.iter().enumerate() {
+        if !endpoint.url.starts_with("http://") && !endpoint.url.starts_with("https://") {
+            issues.push(Issue::new(
+                format!("webhooks.endpoints[{}].url", endpoint_idx),
+                format!("{:?} is not an http:// or https:// URL", endpoint.url)
+            ))
+        }
+    }
+    check_duration(&mut issues, "webhooks.timeoutMs", config.webhooks.timeout_ms);
+    check_duration(&mut issues, "webhooks.retryInitialMs", config.webhooks.retry_initial_ms);
+    check_duration(&mut issues, "webhooks.retryMaxMs", config.webhooks.retry_max_ms);
+    if config.webhooks.max_attempts == 0 {
+        issues.push(Issue::new("webhooks.maxAttempts", "can't be 0"))
+    }
+
+    for event in HookEvent::ALL {
+        for (hook_idx, hook) in config.hooks.of(event).iter().enumerate() {
+            let path = format!("hooks.{}[{}]", event.as_str(), hook_idx);
+
+            if hook.command.trim().is_empty() {
+                issues.push(Issue::new(format!("{}.command", path), "can't be empty"))
+            }
+            check_duration(&mut issues, &format!("{}.timeoutMs", path), hook.timeout_ms);
+        }
+    }
+
+    issues
+}
+
+/// ## Find where each value of a JSON document starts
+///
+/// Keys are paths like `wifi.networks[1].ssid`.
+/// Stops at the first syntax error, keeping what it found.
+pub fn locate(source: &str) -> HashMap<String, Position> {
+    let mut scanner = Scanner {
+        bytes: source.as_bytes(),
+        pos: 0,
+        line: 1,
+        column: 1,
+        positions: HashMap::new()
+    };
+    scanner.value(String::new());
+
+    scanner.positions
+}
+
+/// ## Position of a path, or of its closest parent
+pub fn position_of(positions: &HashMap<String, Position>, path: &str) -> Option<Position> {
+    let mut path = path;
+
+    loop {
+        if let Some(position) = positions.get(path) {
+            return Some(*position)
+        }
+        let parent = path.rfind(['.', '['])?;
+        path = &path[..parent];
+    }
+}
+
+/// ## Log the issues, pointing into the config file
+///
+/// Positions are only known for JSON.
+pub fn log(issues: &[Issue]) {
+    let source = match Format::of(crate::CFG_PATH.as_path()) {
+        Format::Json => std::fs::read_to_string(crate::CFG_PATH.as_path()).ok(),
+        _ => None
+    };
+
+    for line in describe(issues, source.as_deref()) {
+        error!("x   {}", line);
+    }
+}
+
+/// ## Human-readable issues, with positions if `source` is given
+pub fn describe(issues: &[Issue], source: Option<&str>) -> Vec<String> {
+    let positions = source.map(locate).unwrap_or_default();
+
+    issues.iter().map(|issue| match position_of(&positions, &issue.path) {
+        Some(position) => format!(
+            "{} (line {}, column {}): {}",
+            issue.path, position.line, position.column, issue.message
+        ),
+        None => issue.to_string()
+    }).collect()
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, Position>,
+}
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if byte & 0xC0 != 0x80 {
+            // UTF-8 continuation bytes don't start a new column
+            self.column += 1;
+        }
+
+        Some(byte)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.bump();
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let start = self.pos;
+        self.bump();
+
+        loop {
+            match self.bump()? {
+                b'\\' => { self.bump()?; },
+                b'"' => break,
+                _ => ()
+            }
+        }
+
+        let raw = std::str::from_utf8(&self.bytes[start..self.pos]).ok()?;
+        serde_json::from_str::<String>(raw).ok()
+    }
+
+    fn value(&mut self, path: String) -> Option<()> {
+        self.skip_whitespace();
+        self.positions.insert(path.clone(), Position { line: self.line, column: self.column });
+
+        match self.peek()? {
+            b'{' => self.object(path),
+            b'[' => self.array(path),
+            b'"' => self.string().map(|_| ()),
+            _ => {
+                while !matches!(self.peek(), None | Some(b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r')) {
+                    self.bump();
+                }
+                Some(())
+            }
+        }
+    }
+
+    fn object(&mut self, path: String) -> Option<()> {
+        self.bump();
+
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                b'}' => { self.bump(); return Some(()) },
+                b'"' => (),
+                _ => return None
+            }
+
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.bump()? != b':' {
+                return None
+            }
+
+            let child = if path.is_empty() { key } else { format!("{}.{}", path, key) };
+            self.value(child)?;
+
+            self.skip_whitespace();
+            match self.bump()? {
+                b',' => continue,
+                b'}' => return Some(()),
+                _ => return None
+            }
+        }
+    }
+
+    fn array(&mut self, path: String) -> Option<()> {
+        self.bump();
+        let mut idx = 0;
+
+        loop {
+            self.skip_whitespace();
+            if self.peek()? == b']' {
+                self.bump();
+                return Some(())
+            }
+
+            self.value(format!("{}[{}]", path, idx))?;
+            idx += 1;
+
+            self.skip_whitespace();
+            match self.bump()? {
+                b',' => continue,
+                b']' => return Some(()),
+                _ => return None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_network(network: serde_json::Value) -> Config {
+        let mut config = Config::default();
+        config.wifi.networks = vec![serde_json::from_value(network).unwrap()];
+        config
+    }
+
+    #[test]
+    fn malformed_bssid_is_rejected() {
+        let issues = validate(&with_network(serde_json::json!({
+            "ssid": "Office",
+            "password": "amogUSSR",
+            "bssid": "A0:B1:C2:D3:E4"
+        })));
+
+        assert!(issues.iter().any(|issue| issue.path == "wifi.networks[0].bssid"), "{:?}", issues);
+    }
+
+    #[test]
+    fn bssid_takes_both_separators() {
+        for bssid in ["A0:B1:C2:D3:E4:F5", "a0-b1-c2-d3-e4-f5"] {
+            let issues = validate(&with_network(serde_json::json!({
+                "ssid": "Office",
+                "password": "amogUSSR",
+                "bssid": bssid
+            })));
+
+            assert!(!issues.iter().any(|issue| issue.path.ends_with(".bssid")), "{}: {:?}", bssid, issues);
+        }
+    }
+}
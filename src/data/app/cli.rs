@@ -0,0 +1,394 @@
+//! ## Command line
+//!
+//! Without a subcommand, the app runs as usual.
+//! Subcommands do one thing and exit.
+//!
+//! Global options can also be set with `WIFU_*`
+//! environment variables, handy for services and
+//! scheduled tasks, the working directory of which
+//! is hard to control.
+
+use crate::app::cfg::{self, Config};
+use crate::app::{commands, doctor, history, profiles, webhooks};
+use crate::win;
+use crate::win::guid;
+use crate::win::wlan::Interface;
+
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use once_cell::sync::OnceCell;
+use log::{info, error, LevelFilter};
+
+
+pub static OPTIONS: OnceCell<Options> = OnceCell::new();
+
+/// ## Global options, default ones if not parsed yet
+pub fn options() -> &'static Options {
+    OPTIONS.get_or_init(Options::default)
+}
+
+
+#[derive(Debug, Parser)]
+#[command(name = "wifu", version, about = "Keeps your PC connected to the WI-FI networks you trust")]
+pub struct Cli {
+    #[command(flatten)]
+    pub options: Options,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct Options {
+    /// Config file, `cfg.json` (or `cfg.toml`, `cfg.yaml`) in the data directory by default
+    #[arg(long, env = "WIFU_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+    /// Directory for the app's files, `.\wifu-data` by default
+    #[arg(long, env = "WIFU_DATA_DIR", global = true)]
+    pub data_dir: Option<PathBuf>,
+    /// How much to log, `debug` for debug builds and `info` otherwise by default
+    #[arg(long, env = "WIFU_LOG_LEVEL", value_enum, global = true)]
+    pub log_level: Option<LogLevel>,
+    /// How to talk to the WI-FI hardware
+    #[arg(long, env = "WIFU_BACKEND", value_enum, default_value_t, global = true)]
+    pub backend: Backend,
+    /// Do everything but connect, disconnect or change profiles, log what would be done instead
+    #[arg(long, env = "WIFU_DRY_RUN", global = true)]
+    pub dry_run: bool,
+}
+impl Options {
+    pub fn data_dir(&self) -> PathBuf {
+        self.data_dir.clone().unwrap_or_else(|| PathBuf::from_iter([".", "wifu-data"]))
+    }
+
+    pub fn config_path(&self, data_dir: &Path) -> PathBuf {
+        self.config.clone().unwrap_or_else(|| cfg::format::find(data_dir))
+    }
+
+    pub fn open_wlan(&self) -> win::NativeResult<win::Wlan> {
+        let wlan = self.backend.open()?;
+
+        if self.dry_run {
+            Ok(wlan.into_dry_run())
+        } else {
+            Ok(wlan)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+impl LogLevel {
+    pub fn to_filter(&self) -> LevelFilter {
+        match self {
+            Self::Off => LevelFilter::Off,
+            Self::Error => LevelFilter::Error,
+            Self::Warn => LevelFilter::Warn,
+            Self::Info => LevelFilter::Info,
+            Self::Debug => LevelFilter::Debug,
+            Self::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Native Wifi API of Windows (wlanapi.dll)
+    #[default]
+    Native,
+}
+impl Backend {
+    pub fn open(&self) -> win::NativeResult<win::Wlan> {
+        match self {
+            Self::Native => win::Wlan::new(win::wlan::ClientVersion::Second),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage WI-FI profiles stored in Windows
+    #[command(subcommand)]
+    Profiles(ProfilesCommand),
+    /// List wireless interfaces with their GUIDs and states
+    Interfaces,
+    /// Scan and list the networks an interface sees
+    Scan {
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long, visible_alias = "iface")]
+        interface: Option<String>,
+    },
+    /// Show what every interface is connected to
+    Status,
+    /// Connect to a configured network once, creating its profile if needed
+    Connect {
+        /// SSID of a network from `wifi.networks`
+        ssid: String,
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long, visible_alias = "iface")]
+        interface: Option<String>,
+    },
+    /// Check everything from the config to the probe targets and suggest fixes
+    Doctor {
+        /// Interface GUID or (part of) its description, the one the app would choose by default
+        #[arg(short, long, visible_alias = "iface")]
+        interface: Option<String>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Summarize uptime and outages of each network from the history
+    Report {
+        /// Start from a time (`2023-08-01T10:00:00Z`), a local date (`2023-08-01`) or how long ago (`7d`), the whole history by default
+        #[arg(long, value_parser = history::report::parse_since)]
+        since: Option<DateTime<Utc>>,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Send a test payload to every webhook endpoint once
+    TestWebhooks,
+    /// Print a JSON Schema of the config, for editors to validate and autocomplete it
+    Schema {
+        /// Write into a file instead of printing
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+impl Command {
+    /// ## Whether the WLAN API has to be opened first
+    pub fn needs_wlan(&self) -> bool {
+        match self {
+            Self::Profiles(_)
+            | Self::Interfaces
+            | Self::Scan { .. }
+            | Self::Status
+            | Self::Connect { .. }
+            | Self::Doctor { .. } => true,
+            Self::Report { .. }
+            | Self::TestWebhooks
+            | Self::Schema { .. } => false,
+        }
+    }
+
+    /// ## Whether the config has to be loaded first
+    pub fn needs_config(&self) -> bool {
+        match self {
+            Self::Interfaces
+            | Self::Scan { .. }
+            | Self::Status
+            | Self::Connect { .. }
+            | Self::TestWebhooks => true,
+            // doctor loads it on its own, to report problems
+            Self::Profiles(_)
+            | Self::Doctor { .. }
+            | Self::Report { .. }
+            | Self::Schema { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProfilesCommand {
+    /// List profiles of an interface in the order Windows prefers them
+    List {
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long)]
+        interface: Option<String>,
+    },
+    /// Print the XML of a profile, with the key hidden
+    Show {
+        /// Profile name
+        name: String,
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long)]
+        interface: Option<String>,
+        /// Print the key as is (decrypted if run as administrator)
+        #[arg(long)]
+        reveal_key: bool,
+    },
+    /// Delete a profile, so Windows forgets the network
+    Delete {
+        /// Profile name
+        name: String,
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long)]
+        interface: Option<String>,
+    },
+    /// Move a profile in the preference list
+    Move {
+        /// Profile name
+        name: String,
+        /// New position, `0` is the most preferred
+        position: u32,
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long)]
+        interface: Option<String>,
+    },
+    /// Save every profile of an interface into one XML file per profile
+    Export {
+        /// Directory to put the files in
+        dir: PathBuf,
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long)]
+        interface: Option<String>,
+        /// Decrypt the keys (needs admin rights),
+        /// otherwise they can only be imported on the same PC
+        #[arg(long)]
+        plaintext_keys: bool,
+    },
+    /// Store every XML file from a directory as a profile
+    Import {
+        /// Directory with the files
+        dir: PathBuf,
+        /// Interface GUID or (part of) its description, the first one by default
+        #[arg(short, long)]
+        interface: Option<String>,
+        /// Replace profiles with the same name
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+impl ProfilesCommand {
+    pub fn interface(&self) -> Option<&str> {
+        match self {
+            Self::List { interface }
+            | Self::Show { interface, .. }
+            | Self::Delete { interface, .. }
+            | Self::Move { interface, .. }
+            | Self::Export { interface, .. }
+            | Self::Import { interface, .. } => interface.as_deref()
+        }
+    }
+}
+
+/// ## Find an interface by GUID or description
+pub fn resolve_interface(query: Option<&str>) -> Option<Interface> {
+    let wlan = crate::WLAN.get().unwrap();
+    let interfaces = wlan.list_interfaces().ok()?;
+
+    let Some(query) = query else {
+        return interfaces.into_iter().next()
+    };
+    let query = query.trim_matches(|c| c == '{' || c == '}').to_lowercase();
+
+    interfaces.into_iter().find(|iface| {
+        guid::to_string(&iface.guid).to_lowercase() == query
+        || iface.description.to_lowercase().contains(&query)
+    })
+}
+
+/// ## Run a subcommand
+/// 
+/// Returns `false` if it failed.
+pub async fn run(command: Command) -> bool {
+    match command {
+        Command::Profiles(command) => {
+            let Some(iface) = resolve_interface(command.interface()) else {
+                error!("x INTERFACE: no interface matches {:?}", command.interface());
+                return false
+            };
+
+            match command {
+                ProfilesCommand::List { .. } => profiles::list(&iface),
+                ProfilesCommand::Show { name, reveal_key, .. } => profiles::show(&iface, &name, reveal_key),
+                ProfilesCommand::Delete { name, .. } => profiles::delete(&iface, &name),
+                ProfilesCommand::Move { name, position, .. } => profiles::move_to(&iface, &name, position),
+                ProfilesCommand::Export { dir, plaintext_keys, .. } => {
+                    profiles::export(&iface, &dir, plaintext_keys).await
+                },
+                ProfilesCommand::Import { dir, overwrite, .. } => {
+                    profiles::import(&iface, &dir, overwrite).await
+                },
+            }
+        },
+        Command::Interfaces => commands::interfaces(),
+        Command::Scan { interface } => {
+            let Some(iface) = resolve_interface(interface.as_deref()) else {
+                error!("x INTERFACE: no interface matches {:?}", interface);
+                return false
+            };
+            commands::scan(&iface).await
+        },
+        Command::Status => commands::status(),
+        Command::Connect { ssid, interface } => {
+            let Some(iface) = resolve_interface(interface.as_deref()) else {
+                error!("x INTERFACE: no interface matches {:?}", interface);
+                return false
+            };
+            commands::connect(&iface, &ssid).await
+        },
+        Command::Doctor { interface, json } => {
+            let report = doctor::run(interface.as_deref()).await;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            } else {
+                report.print();
+            }
+
+            report.is_ok()
+        },
+        Command::Report { since, json } => report(since, json),
+        Command::TestWebhooks => {
+            let config = crate::config();
+            if config.webhooks.endpoints.is_empty() {
+                error!("x WEBHOOK: there are no webhooks.endpoints in the config");
+                return false
+            }
+            webhooks::test(&config.webhooks.endpoints).await
+        },
+        Command::Schema { output } => schema(output.as_deref()).await,
+    }
+}
+
+fn report(since: Option<DateTime<Utc>>, json: bool) -> bool {
+    let entries = match history::read() {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("x HISTORY: could not read {} ({}), it's written while the app runs", history::path().display(), err);
+            return false
+        }
+    };
+
+    let until = Utc::now();
+    let since = since
+        .or_else(|| entries.first().map(|entry| entry.at))
+        .unwrap_or(until);
+    let report = history::report::compute(&entries, since, until);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        report.print();
+    }
+
+    true
+}
+
+async fn schema(output: Option<&Path>) -> bool {
+    let schema = serde_json::to_string_pretty(&Config::json_schema()).unwrap();
+
+    let Some(output) = output else {
+        println!("{}", schema);
+        return true
+    };
+
+    match tokio::fs::write(output, schema).await {
+        Ok(()) => {
+            info!("o CONFIG: schema written to {}", output.display());
+            true
+        },
+        Err(err) => {
+            error!("x CONFIG: could not write the schema to {} ({})", output.display(), err);
+            false
+        }
+    }
+}
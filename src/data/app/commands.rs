@@ -0,0 +1,187 @@
+//! ## One-shot commands for looking around a site
+//!
+//! They use the same operators as the autopilot,
+//! but do one thing and return.
+
+use crate::app::wlan::{interface, network};
+use crate::win::guid;
+use crate::win::wlan::{Interface, Network};
+
+use log::{info, warn, error};
+
+
+/// ## Networks of a scan, one per SSID, strongest first
+///
+/// Windows lists a network twice if it has a profile,
+/// the entry with the best signal is kept.
+pub fn dedup_by_ssid(mut networks: Vec<Network>) -> Vec<Network> {
+    networks.sort_by_key(|network| std::cmp::Reverse(network.signal_quality));
+
+    let mut unique: Vec<Network> = vec![];
+    for net in networks {
+        if !unique.iter().any(|seen| seen.ssid == net.ssid) {
+            unique.push(net);
+        }
+    }
+
+    unique
+}
+
+fn is_configured(ssid: &str) -> bool {
+    crate::config().wifi.networks.iter().any(|net| net.ssid == ssid)
+}
+
+/// ## Print every wireless interface
+///
+/// The position in `interfaces.priority` is shown
+/// for interfaces that are in it.
+pub fn interfaces() -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    let interfaces = match wlan.list_interfaces() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            error!("x INTERFACE: could not list interfaces ({:?})", err);
+            return false
+        }
+    };
+
+    if interfaces.is_empty() {
+        warn!("! INTERFACE: there are no wireless interfaces");
+        return true
+    }
+
+    let config = crate::config();
+    for iface in interfaces.iter() {
+        let guid = guid::to_string(&iface.guid);
+        let priority = config.interfaces.priority.iter()
+            .position(|prioritized| prioritized.eq_ignore_ascii_case(&guid))
+            .map(|position| position.to_string())
+            .unwrap_or("-".to_string());
+
+        println!(
+            "{}  {:<14}  {:>3}  {}",
+            guid,
+            format!("{:?}", iface.state),
+            priority,
+            iface.description
+        );
+    }
+
+    true
+}
+
+/// ## Scan and print the networks `iface` sees
+pub async fn scan(iface: &Interface) -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    match wlan.scan(&iface.guid).await {
+        Ok(true) => (),
+        Ok(false) => warn!("! NETWORK: scan on {} did not complete, the list may be stale", iface.description),
+        Err(err) => warn!("! NETWORK: could not scan on {} ({:?}), the list may be stale", iface.description, err),
+    }
+
+    let networks = match wlan.available_networks(&iface.guid) {
+        Ok(networks) => dedup_by_ssid(networks),
+        Err(err) => {
+            error!("x NETWORK: could not list networks of {} ({:?})", iface.description, err);
+            return false
+        }
+    };
+
+    println!("{}", iface.description);
+    println!("{:<32}  {:>6}  {:<10}  {:<6}  {:<6}  CONFIGURED", "SSID", "SIGNAL", "AUTH", "CIPHER", "PHY");
+    for net in networks.iter() {
+        let ssid = if net.ssid.is_empty() { "(hidden)" } else { net.ssid.as_str() };
+
+        println!(
+            "{:<32}  {:>5}%  {:<10}  {:<6}  {:<6}  {}",
+            ssid,
+            net.signal_quality,
+            format!("{:?}", net.security.auth),
+            format!("{:?}", net.security.cipher),
+            net.best_generation().map(|gen| format!("{:?}", gen)).unwrap_or("-".to_string()),
+            if is_configured(&net.ssid) { "yes" } else { "" }
+        );
+    }
+
+    true
+}
+
+/// ## Print the connection of every interface
+pub fn status() -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    let interfaces = match wlan.list_interfaces() {
+        Ok(interfaces) => interfaces,
+        Err(err) => {
+            error!("x INTERFACE: could not list interfaces ({:?})", err);
+            return false
+        }
+    };
+
+    if interfaces.is_empty() {
+        warn!("! INTERFACE: there are no wireless interfaces");
+        return true
+    }
+
+    for iface in interfaces.iter() {
+        println!("{} (GUID {})", iface.description, guid::to_string(&iface.guid));
+
+        match wlan.current_connection(&iface.guid) {
+            Ok(Some(conn)) => {
+                println!("  state:      {:?}", conn.state);
+                println!("  ssid:       {}{}", conn.ssid, if is_configured(&conn.ssid) { "" } else { " (not configured)" });
+                println!("  bssid:      {}", conn.bssid);
+                println!("  signal:     {}%", conn.signal_quality);
+                println!("  phy:        {}", conn.phy.generation().map(|gen| format!("{:?}", gen)).unwrap_or("-".to_string()));
+                println!("  rx/tx rate: {} / {} Mbps", conn.rx_rate / 1000, conn.tx_rate / 1000);
+            },
+            Ok(None) => println!("  state:      {:?}", iface.state),
+            Err(err) => println!("  state:      unknown ({:?})", err),
+        }
+    }
+
+    true
+}
+
+/// ## Connect `iface` to a configured network once
+///
+/// Goes through `network::chosen::Operator`, so the
+/// profile is created or fixed up the same way
+/// the autopilot would do it.
+pub async fn connect(iface: &Interface, ssid: &str) -> bool {
+    if !is_configured(ssid) {
+        error!("x NETWORK: {} is not in wifi.networks, add it to the config first", ssid);
+        return false
+    }
+
+    if let Err(err) = interface::LIST.write().await.update() {
+        error!("x INTERFACE: could not list interfaces ({:?})", err);
+        return false
+    }
+    interface::CHOSEN.write().await.choose_interface(iface);
+
+    if let Err(err) = interface::CHOSEN.read().await.scan().await {
+        warn!("! NETWORK: could not scan on {} ({:?})", iface.description, err);
+    }
+    if let Err(err) = network::LIST.write().await.update().await {
+        error!("x NETWORK: could not list networks of {} ({:?})", iface.description, err);
+        return false
+    }
+
+    match network::CHOSEN.write().await.choose_ssid(ssid).await {
+        Ok(true) => {
+            info!("o NETWORK: CONNECTED {} on {}", ssid, iface.description);
+            true
+        },
+        Ok(false) => {
+            error!("x NETWORK: could not connect to {} on {}", ssid, iface.description);
+            false
+        },
+        Err(err) => {
+            error!("x NETWORK: could not connect to {} on {} ({:?})", ssid, iface.description, err);
+            false
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! ## Pausing the autopilot
+//!
+//! While paused, the chosen network is kept no matter
+//! what pings, schedules and scans say, and access
+//! points aren't roamed between. A network is still
+//! chosen if there's none, and switching by hand
+//! (i.e. through the API) works as usual.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::info;
+
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// ## Pause, `false` if it already was
+pub fn pause() -> bool {
+    let was_paused = PAUSED.swap(true, Ordering::SeqCst);
+    if !was_paused {
+        info!("! AUTOPILOT PAUSED, the chosen network is kept until resumed");
+    }
+
+    !was_paused
+}
+
+/// ## Resume, `false` if it wasn't paused
+pub fn resume() -> bool {
+    let was_paused = PAUSED.swap(false, Ordering::SeqCst);
+    if was_paused {
+        info!("o AUTOPILOT RESUMED");
+    }
+
+    was_paused
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
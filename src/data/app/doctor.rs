@@ -0,0 +1,352 @@
+//! ## End-to-end diagnostics
+//!
+//! Runs a checklist from the config down to the
+//! probe targets, and says what to do about
+//! everything that doesn't pass.
+
+use crate::app::cfg::{self, Config, validation};
+use crate::app::state::DeadReason;
+use crate::app::util::domain;
+use crate::app::wlan::{interface, network};
+use crate::win::guid;
+use crate::win::wlan::{Interface, Network};
+use crate::win::wlan::network::profile::{Key, protector};
+
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use serde_derive::Serialize;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+impl Status {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+impl Report {
+    fn pass(&mut self, name: impl ToString, detail: impl ToString) {
+        self.push(name, Status::Pass, detail, None::<String>)
+    }
+
+    fn warn(&mut self, name: impl ToString, detail: impl ToString, fix: impl ToString) {
+        self.push(name, Status::Warn, detail, Some(fix))
+    }
+
+    fn fail(&mut self, name: impl ToString, detail: impl ToString, fix: impl ToString) {
+        self.push(name, Status::Fail, detail, Some(fix))
+    }
+
+    fn push(&mut self, name: impl ToString, status: Status, detail: impl ToString, fix: Option<impl ToString>) {
+        self.checks.push(Check {
+            name: name.to_string(),
+            status,
+            detail: detail.to_string(),
+            fix: fix.map(|fix| fix.to_string())
+        })
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|check| check.status != Status::Fail)
+    }
+
+    pub fn print(&self) {
+        for check in self.checks.iter() {
+            println!("[{}] {}: {}", check.status.label(), check.name, check.detail);
+            if let Some(fix) = check.fix.as_ref() {
+                println!("       ? FIX: {}", fix);
+            }
+        }
+
+        let count = |status: Status| self.checks.iter().filter(|check| check.status == status).count();
+        println!();
+        println!("{} passed, {} warnings, {} failed", count(Status::Pass), count(Status::Warn), count(Status::Fail));
+    }
+}
+
+/// ## Load the config, falling back to defaults for the other checks
+async fn check_config(report: &mut Report) -> bool {
+    let path = crate::CFG_PATH.as_path();
+
+    let config = if !path.exists() {
+        report.fail("config", format!("{} does not exist", path.display()), "run wifu once to create it, then fill in wifi.networks");
+        None
+    } else {
+        match Config::load().await {
+            Ok(config) => Some(config),
+            Err(err) => {
+                report.fail("config", err, format!("fix {} at the reported place", path.display()));
+                None
+            }
+        }
+    };
+
+    let valid = match config.as_ref().map(|config| config.is_valid()) {
+        Some(Ok(())) => {
+            report.pass("config", format!("{} is valid", path.display()));
+            true
+        },
+        Some(Err(issues)) => {
+            let source = match cfg::Format::of(path) {
+                cfg::Format::Json => std::fs::read_to_string(path).ok(),
+                _ => None
+            };
+            for issue in validation::describe(&issues, source.as_deref()) {
+                report.fail("config", issue, "see CFG-DOC.md for what's allowed");
+            }
+            false
+        },
+        None => false
+    };
+
+    crate::CONFIG.set(RwLock::new(Arc::new(config.unwrap_or_default()))).unwrap();
+    valid
+}
+
+/// ## The interface the app would choose, or the one asked for
+async fn check_interface(report: &mut Report, query: Option<&str>) -> Option<Interface> {
+    let mut list = interface::LIST.write().await;
+    if let Err(err) = list.update() {
+        report.fail("interfaces", format!("could not list interfaces ({:?})", err), "check that the WLAN AutoConfig service is running");
+        return None
+    }
+
+    if list.is_empty() {
+        let reason = DeadReason::NoInterface;
+        report.fail("interfaces", reason.message(), reason.fix());
+        return None
+    }
+    report.pass("interfaces", format!("{} found", list.as_slice().len()));
+
+    let iface = match query {
+        Some(query) => crate::app::cli::resolve_interface(Some(query)),
+        None => list.sorted_priority().into_iter().next(),
+    };
+    match iface {
+        Some(iface) => {
+            report.pass("interface", format!("checking {} (GUID {})", iface.description, guid::to_string(&iface.guid)));
+            Some(iface)
+        },
+        None => {
+            report.fail("interface", format!("no interface matches {:?}", query), "run `wifu interfaces` to see them");
+            None
+        }
+    }
+}
+
+/// ## Configured networks the interface sees
+async fn check_networks(report: &mut Report, iface: &Interface) -> Vec<(cfg::Network, Network)> {
+    let wlan = crate::WLAN.get().unwrap();
+    let config = crate::config();
+
+    match wlan.scan(&iface.guid).await {
+        Ok(true) => (),
+        _ => report.warn("scan", "the scan did not complete, results may be stale", "try again, or re-plug the interface"),
+    }
+    let live = match wlan.available_networks(&iface.guid) {
+        Ok(live) => live,
+        Err(err) => {
+            report.fail("scan", format!("could not list networks ({:?})", err), "re-plug the interface");
+            return vec![]
+        }
+    };
+
+    let mut visible = vec![];
+    for cfgs_network in config.wifi.networks.iter() {
+        let name = format!("network {}", cfgs_network.ssid);
+        let live_network = live.iter()
+            .filter(|net| net.ssid == cfgs_network.ssid)
+            .max_by_key(|net| net.signal_quality);
+
+        let Some(live_network) = live_network else {
+            if cfgs_network.hidden {
+                report.warn(name, "hidden, so it's not in the scan list", "run `wifu connect` to check it can be connected to");
+            } else {
+                report.warn(name, "not visible", "check the SSID spelling, or move closer to the access point");
+            }
+            continue
+        };
+
+        if let Err(err) = live_network.check_supported() {
+            report.fail(name, format!("visible, but {}", err), "switch the access point to a WPA2/WPA3 personal or enterprise mode");
+            continue
+        }
+
+        let signal = live_network.signal_quality;
+        if signal < config.wifi.preference.min_quality {
+            report.warn(
+                name,
+                format!("visible, signal {}% is below wifi.preference.minQuality ({}%)", signal, config.wifi.preference.min_quality),
+                "move the interface closer, or lower wifi.preference.minQuality"
+            );
+        } else {
+            report.pass(name, format!("visible, signal {}%", signal));
+        }
+
+        visible.push((cfgs_network.clone(), live_network.clone()));
+    }
+
+    if visible.is_empty() && !config.wifi.networks.iter().any(|net| net.hidden) {
+        let reason = DeadReason::NoNetwork;
+        report.fail("networks", reason.message(), reason.fix());
+    }
+
+    visible
+}
+
+/// ## Stored profiles against what the config would produce
+async fn check_profiles(report: &mut Report, iface: &Interface, visible: &[(cfg::Network, Network)]) {
+    let wlan = crate::WLAN.get().unwrap();
+    let protector = protector::platform();
+
+    for (cfgs_network, live_network) in visible.iter() {
+        let name = format!("profile {}", cfgs_network.ssid);
+
+        let password = match cfgs_network.password().await {
+            Ok(password) => password,
+            Err(err) => {
+                report.fail(name, format!("can't get the password: {}", err), "fix the password source in the config");
+                continue
+            }
+        };
+
+        if !wlan.profile_exists(&iface.guid, &cfgs_network.ssid) {
+            report.pass(name, "not stored yet, it will be created on connect");
+            continue
+        }
+
+        let key = password.as_ref().map(|pwd| Key::from_plain(pwd.expose()));
+        let mut expected = match live_network.clone().to_profile(key) {
+            Ok(expected) => expected,
+            Err(err) => {
+                report.fail(name, err, "switch the access point to a supported security mode");
+                continue
+            }
+        };
+        expected.non_broadcast = cfgs_network.hidden;
+        let expected = network::chosen::Operator::expected_profile(cfgs_network, expected);
+
+        let stored = match wlan.get_profile(&iface.guid, &cfgs_network.ssid, true) {
+            Ok(stored) => stored,
+            Err(err) => {
+                report.warn(name, format!("could not read the stored profile ({:?})", err), "run as administrator");
+                continue
+            }
+        };
+
+        let drifted = network::drift::detect(&stored, &expected, true, protector.as_ref());
+        if drifted.is_empty() {
+            report.pass(name, "matches the config");
+        } else {
+            report.warn(
+                name,
+                format!("drifted from the config ({})", network::drift::describe(&drifted)),
+                format!("it's fixed on the next connect, or run `wifu connect \"{}\"`", cfgs_network.ssid)
+            );
+        }
+    }
+}
+
+/// ## Resolve the probe targets
+fn check_targets(report: &mut Report) -> Vec<(String, SocketAddr)> {
+    let config = crate::config();
+    let mut addrs = vec![];
+
+    for target in config.ping.targets.iter() {
+        match target {
+            cfg::Target::Ip { address } => addrs.push((address.to_string(), SocketAddr::new(*address, 0))),
+            cfg::Target::Domain { name, .. } => {
+                let check = format!("resolve {}", name);
+
+                match domain::http_to_ips(name).map(|ips| ips.collect::<Vec<SocketAddr>>()) {
+                    Ok(ips) if !ips.is_empty() => {
+                        report.pass(check, format!("{} IPs, pinging {}", ips.len(), ips[0].ip()));
+                        addrs.push((name.clone(), ips[0]));
+                    },
+                    Ok(_) => report.fail(check, "no IPs", "check the domain spelling"),
+                    Err(err) => report.fail(check, err, "check the current connection and DNS settings"),
+                }
+            }
+        }
+    }
+
+    addrs
+}
+
+/// ## Ping each target once on whatever link works now
+async fn check_reachability(report: &mut Report, iface: &Interface, addrs: &[(String, SocketAddr)]) {
+    let wlan = crate::WLAN.get().unwrap();
+    let config = crate::config();
+
+    match wlan.current_connection(&iface.guid) {
+        Ok(Some(conn)) => report.pass("link", format!("{} is connected to {}", iface.description, conn.ssid)),
+        _ => report.warn("link", format!("{} is not connected, probes go through another link", iface.description), "run `wifu connect` first to test this interface"),
+    }
+
+    let mut pinger = winping::AsyncPinger::new();
+    pinger.set_timeout(config.ping.timeout_ms);
+
+    let mut reachable = 0;
+    for (name, addr) in addrs.iter() {
+        let check = format!("ping {}", name);
+
+        match pinger.send(addr.ip(), winping::Buffer::new()).await.result {
+            Ok(rtt) => {
+                reachable += 1;
+                report.pass(check, format!("{} answered in {} ms", addr.ip(), rtt));
+            },
+            Err(err) => report.warn(check, format!("{} did not answer ({})", addr.ip(), err), "it may block ICMP, pick another target"),
+        }
+    }
+
+    if !addrs.is_empty() && reachable == 0 {
+        report.fail("probes", "no target answered", "check the current connection, or pick targets that answer pings");
+    }
+}
+
+/// ## Run every check
+///
+/// Checks that depend on a failed one are skipped.
+pub async fn run(interface: Option<&str>) -> Report {
+    let mut report = Report::default();
+
+    check_config(&mut report).await;
+
+    let iface = check_interface(&mut report, interface).await;
+    if let Some(iface) = iface.as_ref() {
+        let visible = check_networks(&mut report, iface).await;
+        check_profiles(&mut report, iface, &visible).await;
+    }
+
+    let addrs = check_targets(&mut report);
+    if let Some(iface) = iface.as_ref() {
+        check_reachability(&mut report, iface, &addrs).await;
+    }
+
+    report
+}
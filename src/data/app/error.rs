@@ -1,4 +1,5 @@
 use num_derive::{FromPrimitive, ToPrimitive};
+use std::fmt;
 
 
 #[derive(Debug, FromPrimitive, ToPrimitive)]
@@ -9,7 +10,19 @@ pub enum Error {
 #[derive(Debug)]
 pub enum RwError {
     ReadError(String),
-    DeserializeError(String)
+    DeserializeError(String),
+    MigrateError(String),
+    WriteError(String),
+}
+impl fmt::Display for RwError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReadError(err) => write!(f, "could not read the file ({})", err),
+            Self::DeserializeError(err) => write!(f, "could not parse the file: {}", err),
+            Self::MigrateError(err) => write!(f, "{}", err),
+            Self::WriteError(err) => write!(f, "could not write the file ({})", err),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
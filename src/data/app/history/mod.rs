@@ -0,0 +1,150 @@
+//! ## Journal of what the app did
+//!
+//! Significant events are appended to `history.jsonl`
+//! in the data directory, one JSON object per line,
+//! so they outlive the console. `wifu report`
+//! computes uptime from it.
+//!
+//! Only the running app writes to it, commands don't.
+//! It's also what webhooks and hooks are run from.
+
+pub mod report;
+
+use crate::app::{DeadReason, hooks, webhooks};
+use crate::app::wlan::network::SwitchReason;
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use chrono::{DateTime, Utc};
+use serde_derive::{Serialize, Deserialize};
+use once_cell::sync::{Lazy, OnceCell};
+use log::{debug, warn};
+
+
+pub const FILE_NAME: &str = "history.jsonl";
+
+static PATH: OnceCell<PathBuf> = OnceCell::new();
+static CONTEXT: Lazy<Mutex<Context>> = Lazy::new(
+    || Mutex::new(Context::default())
+);
+
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum Event {
+    /// ## The app was started
+    Started,
+    InterfaceChosen { guid: String, description: String },
+    InterfaceUnchosen { guid: String, description: String },
+    NetworkChosen { ssid: String, reason: SwitchReason },
+    /// ## A network would've been chosen, in a dry run
+    WouldChoose { ssid: String, reason: SwitchReason },
+    NetworkUnchosen { ssid: String },
+    /// ## `ping.maxErrors` unanswered pings in a row
+    OutageStarted { ssid: Option<String>, target: String },
+    /// ## First answered ping after an outage
+    OutageEnded { ssid: Option<String>, target: String },
+    Dead { reason: DeadReason },
+    Alive,
+}
+
+/// ## What's chosen according to the journal
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Context {
+    pub ssid: Option<String>,
+    /// ## GUID and description
+    pub interface: Option<(String, String)>,
+}
+impl Context {
+    fn apply(&mut self, event: &Event) {
+        match event {
+            Event::InterfaceChosen { guid, description } => self.interface = Some((guid.clone(), description.clone())),
+            Event::InterfaceUnchosen { .. } => self.interface = None,
+            Event::NetworkChosen { ssid, .. } => self.ssid = Some(ssid.clone()),
+            Event::NetworkUnchosen { .. } => self.ssid = None,
+            _ => ()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+pub fn path() -> PathBuf {
+    crate::DATA_PATH.join(FILE_NAME)
+}
+
+/// ## Start writing the journal
+pub fn open() {
+    PATH.set(path()).unwrap();
+    record(Event::Started);
+}
+
+/// ## Network chosen according to the journal
+pub fn current_ssid() -> Option<String> {
+    CONTEXT.lock().unwrap().ssid.clone()
+}
+
+/// ## Append an event, if the journal is open
+///
+/// Webhooks are notified of it afterwards.
+pub fn record(event: Event) {
+    let (before, after) = {
+        let mut context = CONTEXT.lock().unwrap();
+        let before = context.clone();
+        context.apply(&event);
+        (before, context.clone())
+    };
+
+    let Some(path) = PATH.get() else {
+        return
+    };
+
+    let entry = Entry { at: Utc::now(), event };
+    let mut line = serde_json::to_string(&entry).unwrap();
+    line.push('\n');
+    debug!("history: {}", line.trim_end());
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+
+    if let Err(err) = written {
+        warn!("! HISTORY: could not write to {} ({})", path.display(), err);
+    }
+
+    webhooks::notify(&entry, &before, &after);
+    hooks::notify(&entry, &before, &after);
+}
+
+/// ## Every entry of the journal, oldest first
+///
+/// Lines that can't be parsed (i.e. cut off
+/// by a crash) are skipped with a warning.
+pub fn read() -> std::io::Result<Vec<Entry>> {
+    let path = path();
+    let text = std::fs::read_to_string(&path)?;
+
+    let mut entries = vec![];
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue
+        }
+
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => warn!("! HISTORY: skipping line {} of {} ({})", idx + 1, path.display(), err),
+        }
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,370 @@
+//! ## Uptime of each network, from the journal
+//!
+//! A network is up while it's chosen, except
+//! during probe outages. Time between the last
+//! event before a restart and the restart itself
+//! isn't counted, since the app wasn't watching.
+
+use super::{Entry, Event};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use serde_derive::Serialize;
+
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStats {
+    pub ssid: String,
+    /// ## How long it was chosen
+    pub chosen_secs: i64,
+    pub outages: u32,
+    /// ## How long outages lasted in total
+    pub downtime_secs: i64,
+}
+impl NetworkStats {
+    fn new(ssid: &str) -> Self {
+        Self { ssid: ssid.to_string(), chosen_secs: 0, outages: 0, downtime_secs: 0 }
+    }
+
+    pub fn up_secs(&self) -> i64 {
+        (self.chosen_secs - self.downtime_secs).max(0)
+    }
+
+    /// ## Share of the chosen time without outages, in percent
+    pub fn uptime(&self) -> Option<f64> {
+        if self.chosen_secs == 0 {
+            return None
+        }
+
+        Some(self.up_secs() as f64 / self.chosen_secs as f64 * 100.0)
+    }
+
+    /// ## Mean time between failures, i.e. outages
+    pub fn mtbf_secs(&self) -> Option<i64> {
+        if self.outages == 0 {
+            return None
+        }
+
+        Some(self.up_secs() / self.outages as i64)
+    }
+
+    pub fn mean_outage_secs(&self) -> Option<i64> {
+        if self.outages == 0 {
+            return None
+        }
+
+        Some(self.downtime_secs / self.outages as i64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub networks: Vec<NetworkStats>,
+}
+impl Report {
+    pub fn print(&self) {
+        println!(
+            "From {} to {} ({})",
+            self.since.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            self.until.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            format_secs((self.until - self.since).num_seconds())
+        );
+
+        if self.networks.is_empty() {
+            println!("No network was chosen in this time");
+            return
+        }
+
+        println!();
+        println!(
+            "{:<32}  {:>10}  {:>8}  {:>7}  {:>10}  {:>10}  {:>10}",
+            "NETWORK", "CHOSEN", "UPTIME", "OUTAGES", "DOWNTIME", "AVG OUTAGE", "MTBF"
+        );
+        for net in self.networks.iter() {
+            let optional = |secs: Option<i64>| secs.map(format_secs).unwrap_or("-".to_string());
+
+            println!(
+                "{:<32}  {:>10}  {:>8}  {:>7}  {:>10}  {:>10}  {:>10}",
+                net.ssid,
+                format_secs(net.chosen_secs),
+                net.uptime().map(|uptime| format!("{:.2}%", uptime)).unwrap_or("-".to_string()),
+                net.outages,
+                format_secs(net.downtime_secs),
+                optional(net.mean_outage_secs()),
+                optional(net.mtbf_secs())
+            );
+        }
+    }
+}
+
+/// ## Running totals while going through the journal
+struct Tally {
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    networks: Vec<NetworkStats>,
+    /// ## Chosen network, since when
+    network: Option<(String, DateTime<Utc>)>,
+    /// ## Network of an ongoing outage, since when
+    outage: Option<(Option<String>, DateTime<Utc>)>,
+}
+impl Tally {
+    fn stats(&mut self, ssid: &str) -> &mut NetworkStats {
+        if let Some(idx) = self.networks.iter().position(|net| net.ssid == ssid) {
+            return &mut self.networks[idx]
+        }
+
+        self.networks.push(NetworkStats::new(ssid));
+        self.networks.last_mut().unwrap()
+    }
+
+    /// ## Part of `from..to` inside the report
+    fn clip(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Option<Duration> {
+        let from = from.max(self.since);
+        let to = to.min(self.until);
+
+        if to < from { None } else { Some(to - from) }
+    }
+
+    fn close_network(&mut self, at: DateTime<Utc>) {
+        let Some((ssid, from)) = self.network.take() else {
+            return
+        };
+
+        if let Some(chosen) = self.clip(from, at) {
+            self.stats(&ssid).chosen_secs += chosen.num_seconds();
+        }
+    }
+
+    fn close_outage(&mut self, at: DateTime<Utc>) {
+        let Some((ssid, from)) = self.outage.take() else {
+            return
+        };
+        let Some(ssid) = ssid else {
+            return
+        };
+
+        if let Some(downtime) = self.clip(from, at) {
+            let stats = self.stats(&ssid);
+            stats.outages += 1;
+            stats.downtime_secs += downtime.num_seconds();
+        }
+    }
+}
+
+/// ## Go through the journal, counting only `since..until`
+pub fn compute(entries: &[Entry], since: DateTime<Utc>, until: DateTime<Utc>) -> Report {
+    let mut tally = Tally { since, until, networks: vec![], network: None, outage: None };
+    let mut last_at = None;
+
+    for entry in entries.iter() {
+        let at = entry.at;
+
+        match &entry.event {
+            Event::Started => {
+                let stopped_at = last_at.unwrap_or(at);
+                tally.close_outage(stopped_at);
+                tally.close_network(stopped_at);
+            },
+            Event::NetworkChosen { ssid, .. } => {
+                tally.close_outage(at);
+                tally.close_network(at);
+                tally.network = Some((ssid.clone(), at));
+            },
+            Event::NetworkUnchosen { .. } | Event::Dead { .. } => {
+                tally.close_outage(at);
+                tally.close_network(at);
+            },
+            Event::OutageStarted { ssid, .. } if tally.outage.is_none() => {
+                let ssid = ssid.clone().or_else(|| tally.network.as_ref().map(|(ssid, _)| ssid.clone()));
+                tally.outage = Some((ssid, at));
+            },
+            Event::OutageEnded { .. } => tally.close_outage(at),
+            _ => ()
+        }
+
+        last_at = Some(at);
+    }
+
+    tally.close_outage(until);
+    tally.close_network(until);
+
+    Report { since, until, networks: tally.networks }
+}
+
+/// ## Parse `--since`
+///
+/// Takes a time like `2023-08-01T10:00:00+03:00`,
+/// a local date like `2023-08-01`, or how long
+/// ago like `30m`, `12h`, `7d` or `2w`.
+pub fn parse_since(since: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(since) {
+        return Ok(time.with_timezone(&Utc))
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        return Local.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or(format!("{} has no midnight in the local time zone", since))
+    }
+
+    let invalid = || format!("{:?} is not a time, a date or how long ago (like 7d)", since);
+    let unit = since.chars().last().ok_or_else(invalid)?;
+    let amount = since[..since.len() - unit.len_utf8()].parse::<i64>().map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(format!("{:?} is not in the past", since))
+    }
+
+    let unit_secs = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return Err(invalid())
+    };
+    let too_long = || format!("{:?} is too long ago", since);
+
+    // `Duration` keeps milliseconds, and panics beyond them
+    let secs = amount.checked_mul(unit_secs)
+        .filter(|secs| *secs <= i64::MAX / 1000)
+        .ok_or_else(too_long)?;
+
+    Utc::now().checked_sub_signed(Duration::seconds(secs)).ok_or_else(too_long)
+}
+
+/// ## Like `3d 4h`, the two biggest units
+pub fn format_secs(secs: i64) -> String {
+    let (days, hours, mins) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, mins)
+    } else if mins > 0 {
+        format!("{}m {}s", mins, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::DeadReason;
+    use crate::app::wlan::network::SwitchReason;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2023-08-01T10:00:00Z").unwrap().with_timezone(&Utc) + Duration::seconds(secs)
+    }
+
+    fn entry(secs: i64, event: Event) -> Entry {
+        Entry { at: at(secs), event }
+    }
+
+    fn chosen(ssid: &str) -> Event {
+        Event::NetworkChosen { ssid: ssid.to_string(), reason: SwitchReason::Startup }
+    }
+
+    fn outage(started: bool) -> Event {
+        let (ssid, target) = (None, "1.1.1.1".to_string());
+        if started { Event::OutageStarted { ssid, target } } else { Event::OutageEnded { ssid, target } }
+    }
+
+    #[test]
+    fn outages_count_against_the_chosen_network() {
+        let entries = [
+            entry(0, Event::Started),
+            entry(0, chosen("Office")),
+            entry(100, outage(true)),
+            entry(110, outage(false)),
+            entry(150, chosen("Backup")),
+        ];
+        let report = compute(&entries, at(0), at(200));
+
+        let office = &report.networks[0];
+        assert_eq!((office.ssid.as_str(), office.chosen_secs), ("Office", 150));
+        assert_eq!((office.outages, office.downtime_secs), (1, 10));
+        assert_eq!(office.mtbf_secs(), Some(140));
+        assert_eq!(office.mean_outage_secs(), Some(10));
+
+        let backup = &report.networks[1];
+        assert_eq!((backup.ssid.as_str(), backup.chosen_secs, backup.outages), ("Backup", 50, 0));
+        assert_eq!(backup.uptime(), Some(100.0));
+        assert_eq!(backup.mtbf_secs(), None);
+    }
+
+    #[test]
+    fn time_while_stopped_is_not_counted() {
+        let entries = [
+            entry(0, chosen("Office")),
+            entry(30, outage(true)),
+            entry(50, Event::Alive),
+            entry(1000, Event::Started),
+            entry(1000, chosen("Office")),
+        ];
+        let report = compute(&entries, at(0), at(1100));
+
+        let office = &report.networks[0];
+        assert_eq!(office.chosen_secs, 50 + 100);
+        assert_eq!((office.outages, office.downtime_secs), (1, 20));
+    }
+
+    #[test]
+    fn dead_ends_the_chosen_time() {
+        let entries = [
+            entry(0, chosen("Office")),
+            entry(60, Event::Dead { reason: DeadReason::NoNetwork }),
+        ];
+        let report = compute(&entries, at(0), at(600));
+
+        assert_eq!(report.networks[0].chosen_secs, 60);
+    }
+
+    #[test]
+    fn only_the_report_window_is_counted() {
+        let entries = [
+            entry(0, chosen("Office")),
+            entry(50, outage(true)),
+            entry(150, outage(false)),
+        ];
+        let report = compute(&entries, at(100), at(200));
+
+        let office = &report.networks[0];
+        assert_eq!(office.chosen_secs, 100);
+        assert_eq!((office.outages, office.downtime_secs), (1, 50));
+    }
+
+    #[test]
+    fn dry_run_choices_are_not_counted() {
+        let entries = [
+            entry(0, Event::WouldChoose { ssid: "Office".to_string(), reason: SwitchReason::Startup }),
+        ];
+
+        assert!(compute(&entries, at(0), at(100)).networks.is_empty());
+    }
+
+    #[test]
+    fn since_takes_times_dates_and_ago() {
+        assert_eq!(parse_since("2023-08-01T13:00:00+03:00").unwrap(), at(0));
+        assert!(parse_since("2023-08-01").is_ok());
+
+        let week_ago = Utc::now() - Duration::weeks(1);
+        let parsed = parse_since("1w").unwrap();
+        assert!((parsed - week_ago).num_seconds().abs() < 5);
+
+        for (since, secs) in [("30m", 30 * 60), ("12h", 12 * 60 * 60), ("7d", 7 * 24 * 60 * 60)] {
+            let ago = Utc::now() - parse_since(since).unwrap();
+            assert!((ago.num_seconds() - secs).abs() < 5, "{}", since);
+        }
+    }
+
+    #[test]
+    fn since_rejects_nonsense() {
+        for since in ["", "d", "7", "7y", "7д", "0d", "-5d", "99999999999d", "9223372036854775807w"] {
+            assert!(parse_since(since).is_err(), "{}", since);
+        }
+    }
+}
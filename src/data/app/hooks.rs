@@ -0,0 +1,206 @@
+//! ## User-defined hook commands
+//!
+//! Runs the commands of `hooks.<event>` when the
+//! event is journaled, with `WIFU_*` environment
+//! variables describing it. Events are queued and
+//! their hooks run one at a time, in order, so the
+//! switcher never waits for them.
+
+use crate::app::cfg::{Hook, HookEvent, OnFailure};
+use crate::app::history::{Context, Entry, Event};
+use crate::app::wlan::event;
+
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::{Notify, RwLock};
+use tokio::task::JoinHandle;
+use once_cell::sync::Lazy;
+use log::{debug, info, warn, error};
+
+
+pub static HANDLE: Lazy<Arc<RwLock<Option<JoinHandle<()>>>>> = Lazy::new(
+    || Arc::new(RwLock::new(None))
+);
+static QUEUE: Lazy<Mutex<VecDeque<Job>>> = Lazy::new(
+    || Mutex::new(VecDeque::new())
+);
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// ## Pause before a hook is run again
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+
+struct Job {
+    event: HookEvent,
+    env: Vec<(&'static str, String)>,
+}
+
+/// ## Event and its variables, if it has hooks
+fn job(entry: &Entry, before: &Context, after: &Context) -> Option<Job> {
+    let mut env = vec![];
+    let mut ssid = after.ssid.clone();
+    let mut iface = after.interface.clone();
+
+    let event = match &entry.event {
+        Event::NetworkChosen { reason, .. } => {
+            env.push(("WIFU_SWITCH_REASON", reason.as_str().to_string()));
+            HookEvent::NetworkChosen
+        },
+        Event::NetworkUnchosen { ssid: unchosen } => {
+            ssid = Some(unchosen.clone());
+            HookEvent::NetworkUnchosen
+        },
+        Event::InterfaceChosen { guid, description } => {
+            iface = Some((guid.clone(), description.clone()));
+            HookEvent::InterfaceChosen
+        },
+        Event::Dead { reason } => {
+            env.push(("WIFU_DEAD_REASON", reason.as_str().to_string()));
+            HookEvent::Dead
+        },
+        Event::Alive => HookEvent::Alive,
+        Event::OutageStarted { target, .. } => {
+            env.push(("WIFU_OUTAGE", "started".to_string()));
+            env.push(("WIFU_PROBE_TARGET", target.clone()));
+            HookEvent::ProbeOutage
+        },
+        Event::OutageEnded { target, .. } => {
+            env.push(("WIFU_OUTAGE", "ended".to_string()));
+            env.push(("WIFU_PROBE_TARGET", target.clone()));
+            HookEvent::ProbeOutage
+        },
+        _ => return None
+    };
+
+    env.push(("WIFU_EVENT", event.as_str().to_string()));
+    if let Some(ssid) = ssid {
+        env.push(("WIFU_SSID", ssid));
+    }
+    if let Some(prev_ssid) = before.ssid.clone() {
+        env.push(("WIFU_PREV_SSID", prev_ssid));
+    }
+    if let Some((guid, description)) = iface {
+        env.push(("WIFU_IFACE_GUID", guid));
+        env.push(("WIFU_IFACE_DESCRIPTION", description));
+    }
+
+    Some(Job { event, env })
+}
+
+/// ## Queue the hooks of a journal entry
+pub fn notify(entry: &Entry, before: &Context, after: &Context) {
+    let Some(job) = job(entry, before, after) else {
+        return
+    };
+    if crate::config().hooks.of(job.event).is_empty() {
+        return
+    }
+
+    QUEUE.lock().unwrap().push_back(job);
+    WAKE.notify_one();
+}
+
+/// ## Log each line of a command's output
+fn log_output(name: &str, stdout: &[u8], stderr: &[u8]) {
+    for line in String::from_utf8_lossy(stdout).lines().filter(|line| !line.trim().is_empty()) {
+        info!("o HOOK {}: {}", name, line);
+    }
+    for line in String::from_utf8_lossy(stderr).lines().filter(|line| !line.trim().is_empty()) {
+        warn!("! HOOK {}: {}", name, line);
+    }
+}
+
+/// ## Run a hook once, `Err` with what went wrong
+async fn run_once(event: HookEvent, hook: &Hook, env: &[(&'static str, String)]) -> Result<(), String> {
+    #[cfg(windows)]
+    let (shell, flag) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (shell, flag) = ("sh", "-c");
+
+    let mut command = Command::new(shell);
+    command
+        .args([flag, hook.command.as_str()])
+        .envs(env.iter().map(|(key, value)| (*key, value.as_str())))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let child = command.spawn().map_err(|err| format!("could not start it ({})", err))?;
+    let timeout = Duration::from_millis(hook.timeout_ms);
+
+    // the child is killed if it's dropped on timeout
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|err| format!("could not wait for it ({})", err))?,
+        Err(_) => return Err(format!("timed out after {} ms and was killed", hook.timeout_ms)),
+    };
+
+    log_output(event.as_str(), &output.stdout, &output.stderr);
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {}", output.status))
+    }
+}
+
+/// ## Run a hook, retrying it if it says so
+///
+/// Returns `false` if the remaining hooks should be skipped.
+async fn run(event: HookEvent, hook: &Hook, env: &[(&'static str, String)]) -> bool {
+    if crate::WLAN.get().map(|wlan| wlan.is_dry_run()).unwrap_or(false) {
+        info!("o DRY RUN: would run {} hook `{}`", event.as_str(), hook.command);
+        return true
+    }
+
+    let attempts = match hook.on_failure {
+        OnFailure::Retry => hook.retries + 1,
+        _ => 1
+    };
+
+    for attempt in 1..=attempts {
+        debug!("hooks: running {} hook `{}` ({}/{})", event.as_str(), hook.command, attempt, attempts);
+
+        let err = match run_once(event, hook, env).await {
+            Ok(()) => {
+                info!("o HOOK {}: `{}` succeeded", event.as_str(), hook.command);
+                return true
+            },
+            Err(err) => err,
+        };
+
+        if attempt < attempts {
+            warn!("! HOOK {}: `{}` {}, retrying", event.as_str(), hook.command, err);
+            tokio::time::sleep(RETRY_DELAY).await;
+        } else {
+            error!("x HOOK {}: `{}` {}", event.as_str(), hook.command, err);
+        }
+    }
+
+    hook.on_failure != OnFailure::Abort
+}
+
+pub async fn event_loop() {
+    loop {
+        let next = QUEUE.lock().unwrap().pop_front();
+        let Some(job) = next else {
+            WAKE.notified().await;
+            continue
+        };
+
+        let config = crate::config();
+        for hook in config.hooks.of(job.event).iter() {
+            if !run(job.event, hook, &job.env).await {
+                warn!("! HOOK {}: skipping the remaining hooks", job.event.as_str());
+                break
+            }
+        }
+    }
+}
+
+event::looping::works!(async fn works(HANDLE));
+event::looping::spawner!(async fn spawn_event_loop(HANDLE, event_loop, works));
+event::looping::closer!(async fn close_event_loop(HANDLE));
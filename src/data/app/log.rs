@@ -30,25 +30,23 @@ impl Color {
 pub struct Logger;
 
 impl Logger {
-    pub fn init() -> Result<(), SetLoggerError>  {
+    #[cfg(debug_assertions)]
+    pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::Debug;
+    #[cfg(not(debug_assertions))]
+    pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+    pub fn init(level: LevelFilter) -> Result<(), SetLoggerError>  {
         #[cfg(windows)] {
             let _varname = colored::control::set_virtual_terminal(true).unwrap_or(());
         }
 
         log::set_logger(&crate::LOGGER)
-            .map(|()| log::set_max_level(LevelFilter::Debug))
+            .map(|()| log::set_max_level(level))
     }
 }
 impl Log for Logger {
-    #[cfg(debug_assertions)]
-    fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= Level::Debug
-        && metadata.target().starts_with("wifu")
-    }
-    
-    #[cfg(not(debug_assertions))]
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= Level::Info
+        metadata.level() <= log::max_level()
         && metadata.target().starts_with("wifu")
     }
 
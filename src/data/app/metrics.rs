@@ -0,0 +1,216 @@
+//! ## Prometheus metrics
+//!
+//! Counters are kept in memory since the start,
+//! gauges are read from the operators when scraped.
+//! Served as text on `GET /metrics` of `metrics.listen`.
+
+use crate::app::{self, State};
+use crate::app::wlan::{event, network};
+use crate::app::wlan::network::SwitchReason;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use axum::Router;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use once_cell::sync::Lazy;
+use log::{info, error};
+
+
+pub static HANDLE: Lazy<Arc<RwLock<Option<JoinHandle<()>>>>> = Lazy::new(
+    || Arc::new(RwLock::new(None))
+);
+static METRICS: Lazy<Mutex<Metrics>> = Lazy::new(
+    || Mutex::new(Metrics::default())
+);
+
+/// ## Upper bounds of the probe RTT buckets, in seconds
+const RTT_BUCKETS: [f64; 11] = [0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0];
+
+
+#[derive(Default)]
+struct Histogram {
+    /// ## Observations in each of `RTT_BUCKETS`, not cumulative
+    buckets: [u64; RTT_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(idx) = RTT_BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[idx] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Metrics {
+    switches: BTreeMap<&'static str, u64>,
+    connect_attempts: BTreeMap<String, u64>,
+    connect_failures: BTreeMap<String, u64>,
+    probe_failures: BTreeMap<String, u64>,
+    interface_arrivals: u64,
+    interface_removals: u64,
+    probe_rtt: Histogram,
+    last_switch: Option<Instant>,
+}
+
+fn with(f: impl FnOnce(&mut Metrics)) {
+    f(&mut METRICS.lock().unwrap())
+}
+
+pub fn switched(reason: SwitchReason) {
+    with(|metrics| {
+        *metrics.switches.entry(reason.as_str()).or_insert(0) += 1;
+        metrics.last_switch = Some(Instant::now());
+    })
+}
+
+pub fn connect_attempted(ssid: &str) {
+    with(|metrics| *metrics.connect_attempts.entry(ssid.to_string()).or_insert(0) += 1)
+}
+
+pub fn connect_failed(ssid: &str) {
+    with(|metrics| *metrics.connect_failures.entry(ssid.to_string()).or_insert(0) += 1)
+}
+
+pub fn probe_succeeded(rtt_ms: u32) {
+    with(|metrics| metrics.probe_rtt.observe(rtt_ms as f64 / 1000.0))
+}
+
+pub fn probe_failed(target: &str) {
+    with(|metrics| *metrics.probe_failures.entry(target.to_string()).or_insert(0) += 1)
+}
+
+pub fn interface_arrived() {
+    with(|metrics| metrics.interface_arrivals += 1)
+}
+
+pub fn interface_removed() {
+    with(|metrics| metrics.interface_removals += 1)
+}
+
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn help(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+}
+
+fn labelled<V: std::fmt::Display>(out: &mut String, name: &str, label: &str, values: impl IntoIterator<Item = (impl AsRef<str>, V)>) {
+    for (key, value) in values {
+        writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, escape(key.as_ref()), value).unwrap();
+    }
+}
+
+/// ## Label of the app state, as in `GET /status`
+fn state_label(state: &State) -> &'static str {
+    match state {
+        State::Alive => "alive",
+        State::Dead(reason) => reason.as_str(),
+    }
+}
+
+/// ## Everything in the Prometheus text format
+pub async fn render() -> String {
+    let mut out = String::new();
+
+    {
+        let metrics = METRICS.lock().unwrap();
+
+        help(&mut out, "wifu_switches_total", "counter", "Networks chosen, by the reason");
+        labelled(&mut out, "wifu_switches_total", "reason", metrics.switches.iter().map(|(reason, count)| (*reason, *count)));
+
+        help(&mut out, "wifu_connect_attempts_total", "counter", "Connection attempts, by SSID");
+        labelled(&mut out, "wifu_connect_attempts_total", "ssid", metrics.connect_attempts.iter());
+
+        help(&mut out, "wifu_connect_failures_total", "counter", "Failed connection attempts, by SSID");
+        labelled(&mut out, "wifu_connect_failures_total", "ssid", metrics.connect_failures.iter());
+
+        help(&mut out, "wifu_probe_failures_total", "counter", "Pings without an answer, by target IP");
+        labelled(&mut out, "wifu_probe_failures_total", "target", metrics.probe_failures.iter());
+
+        help(&mut out, "wifu_interface_arrivals_total", "counter", "Wireless interfaces plugged in");
+        writeln!(out, "wifu_interface_arrivals_total {}", metrics.interface_arrivals).unwrap();
+
+        help(&mut out, "wifu_interface_removals_total", "counter", "Wireless interfaces unplugged");
+        writeln!(out, "wifu_interface_removals_total {}", metrics.interface_removals).unwrap();
+
+        help(&mut out, "wifu_probe_rtt_seconds", "histogram", "Round trip time of answered pings");
+        let mut cumulative = 0;
+        for (bound, count) in RTT_BUCKETS.iter().zip(metrics.probe_rtt.buckets.iter()) {
+            cumulative += count;
+            writeln!(out, "wifu_probe_rtt_seconds_bucket{{le=\"{}\"}} {}", bound, cumulative).unwrap();
+        }
+        writeln!(out, "wifu_probe_rtt_seconds_bucket{{le=\"+Inf\"}} {}", metrics.probe_rtt.count).unwrap();
+        writeln!(out, "wifu_probe_rtt_seconds_sum {}", metrics.probe_rtt.sum).unwrap();
+        writeln!(out, "wifu_probe_rtt_seconds_count {}", metrics.probe_rtt.count).unwrap();
+
+        if let Some(last_switch) = metrics.last_switch {
+            help(&mut out, "wifu_seconds_since_last_switch", "gauge", "Seconds since a network was last chosen");
+            writeln!(out, "wifu_seconds_since_last_switch {}", last_switch.elapsed().as_secs()).unwrap();
+        }
+    }
+
+    let config = crate::config();
+    let signals = {
+        let list = network::LIST.read().await;
+        config.wifi.networks.iter()
+            .filter_map(|cfg_net| list.as_slice().iter()
+                .filter(|live_net| live_net.ssid == cfg_net.ssid)
+                .map(|live_net| live_net.signal_quality)
+                .max()
+                .map(|signal| (cfg_net.ssid.clone(), signal))
+            )
+            .collect::<Vec<(String, u32)>>()
+    };
+    help(&mut out, "wifu_network_signal_quality", "gauge", "Signal quality of visible configured networks, 0 to 100");
+    labelled(&mut out, "wifu_network_signal_quality", "ssid", signals);
+
+    let current = state_label(app::STATE.read().await.get());
+    help(&mut out, "wifu_state", "gauge", "1 for the current state of the app");
+    labelled(
+        &mut out,
+        "wifu_state",
+        "state",
+        ["alive", "uninitialized", "noInterface", "noNetwork"].map(|state| (state, (state == current) as u8))
+    );
+
+    out
+}
+
+async fn serve_metrics() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], render().await)
+}
+
+pub async fn event_loop() {
+    let addr = crate::config().metrics.listen;
+
+    let server = match axum::Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(err) => {
+            error!("x METRICS: could not listen on {} ({})", addr, err);
+            return
+        }
+    };
+    info!("o METRICS: LISTENING on http://{}/metrics", addr);
+
+    let router = Router::new().route("/metrics", get(serve_metrics));
+    if let Err(err) = server.serve(router.into_make_service()).await {
+        error!("x METRICS: stopped ({})", err);
+    }
+}
+
+event::looping::works!(async fn works(HANDLE));
+event::looping::spawner!(async fn spawn_event_loop(HANDLE, event_loop, works));
+event::looping::closer!(async fn close_event_loop(HANDLE));
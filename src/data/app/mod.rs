@@ -5,23 +5,69 @@ pub mod cfg;
 pub mod state;
 pub mod error;
 pub mod log;
+pub mod cli;
+pub mod secret;
+pub mod profiles;
+pub mod commands;
+pub mod doctor;
+pub mod reload;
+pub mod control;
+pub mod api;
+pub mod metrics;
+pub mod history;
+pub mod webhooks;
+pub mod hooks;
 pub use pinger::{Pinger, PingErr, PingOk};
 pub use state::{STATE, State, DeadReason};
 pub use error::{Result, Error};
 
 use wlan::{interface, network};
 
+use std::sync::{Arc, RwLock};
+use ::log::error;
+
 
 pub async fn init_fs() -> bool {
     if !crate::DATA_PATH.exists() {
-        tokio::fs::create_dir(crate::DATA_PATH.as_path()).await.unwrap();
+        tokio::fs::create_dir_all(crate::DATA_PATH.as_path()).await.unwrap();
+    }
+    if let Some(cfg_dir) = crate::CFG_PATH.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(cfg_dir).await.unwrap();
     }
 
     if !crate::CFG_PATH.exists() {
-        crate::CONFIG.set(cfg::Config::default_and_save().await.unwrap()).unwrap();
+        crate::CONFIG.set(RwLock::new(Arc::new(cfg::Config::default_and_save().await.unwrap()))).unwrap();
         return true;
     } else {
-        crate::CONFIG.set(cfg::Config::load().await.unwrap()).unwrap();
+        let config = match cfg::Config::load_and_upgrade().await {
+            Ok(config) => config,
+            Err(err) => {
+                error!("x CONFIG at {}: {}", crate::CFG_PATH.as_path().display(), err);
+                std::process::exit(1);
+            }
+        };
+        crate::CONFIG.set(RwLock::new(Arc::new(config))).unwrap();
         return false;
     }
 }
+
+/// ## Load the config for a command
+///
+/// Unlike `init_fs`, nothing is created,
+/// defaults are used if there's no config file.
+pub async fn load_config() -> bool {
+    let config = if crate::CFG_PATH.exists() {
+        match cfg::Config::load().await {
+            Ok(config) => config,
+            Err(err) => {
+                error!("x CONFIG at {}: {}", crate::CFG_PATH.as_path().display(), err);
+                return false
+            }
+        }
+    } else {
+        cfg::Config::default()
+    };
+
+    crate::CONFIG.set(RwLock::new(Arc::new(config))).unwrap();
+    true
+}
@@ -1,16 +1,97 @@
-use super::cfg;
+use super::{cfg, history};
 
-use std::{net::SocketAddr, time::Duration};
-use std::sync::Arc;
+use std::{net::{IpAddr, SocketAddr}, time::Duration};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::RwLock;
 use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
+use serde_derive::Serialize;
 use winping::AsyncPinger;
 use log::debug;
 
 
 pub static PINGER: Lazy<Arc<RwLock<Pinger>>> = Lazy::new(
-    || Arc::new(RwLock::new(Pinger::from_config(crate::CONFIG.get().unwrap().ping.clone())))
+    || Arc::new(RwLock::new(Pinger::from_config(crate::config().ping.clone())))
 );
+/// ## Latest pings, oldest first
+pub static PROBES: Lazy<Mutex<VecDeque<Probe>>> = Lazy::new(
+    || Mutex::new(VecDeque::with_capacity(Probe::KEPT))
+);
+
+/// ## Whether `ping.maxErrors` pings in a row went unanswered
+static IN_OUTAGE: AtomicBool = AtomicBool::new(false);
+
+/// ## Result of a single ping
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Probe {
+    pub address: IpAddr,
+    pub rtt_ms: Option<u32>,
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+impl Probe {
+    /// ## How many of the latest pings are kept
+    pub const KEPT: usize = 20;
+
+    fn record(self) {
+        match self.rtt_ms {
+            Some(rtt) => super::metrics::probe_succeeded(rtt),
+            None => super::metrics::probe_failed(&self.address.to_string()),
+        }
+
+        let mut probes = PROBES.lock().unwrap();
+        if probes.len() >= Self::KEPT {
+            probes.pop_front();
+        }
+        probes.push_back(self);
+    }
+
+    pub fn latest() -> Vec<Probe> {
+        PROBES.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// ## Summary of the latest pings
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeStats {
+    pub sent: usize,
+    pub answered: usize,
+    pub avg_rtt_ms: Option<u32>,
+    pub last_error: Option<String>,
+}
+impl ProbeStats {
+    pub fn latest() -> Self {
+        let probes = PROBES.lock().unwrap();
+        let rtts = probes.iter().filter_map(|probe| probe.rtt_ms).collect::<Vec<u32>>();
+
+        Self {
+            sent: probes.len(),
+            answered: rtts.len(),
+            avg_rtt_ms: (!rtts.is_empty()).then(|| rtts.iter().sum::<u32>() / rtts.len() as u32),
+            last_error: probes.iter().rev().find_map(|probe| probe.error.clone()),
+        }
+    }
+}
+
+/// ## Journal the start or the end of an outage
+///
+/// Only if it's not already started or ended.
+fn set_outage(outage: bool, address: &SocketAddr) {
+    if IN_OUTAGE.swap(outage, Ordering::SeqCst) == outage {
+        return
+    }
+
+    let (ssid, target) = (history::current_ssid(), address.ip().to_string());
+    history::record(if outage {
+        history::Event::OutageStarted { ssid, target }
+    } else {
+        history::Event::OutageEnded { ssid, target }
+    });
+}
 
 pub struct PingOk {
     pub buf: winping::Buffer,
@@ -30,7 +111,7 @@ pub struct Pinger {
 }
 impl Pinger {
     pub fn from_config(config: super::cfg::Ping) -> Self {
-        let ips = Self::gather_ips(&config.domains);
+        let ips = Self::gather_ips(&config.targets);
         let mut pinger = AsyncPinger::new();
         pinger.set_timeout(config.timeout_ms);
 
@@ -41,10 +122,18 @@ impl Pinger {
         self.ips.is_empty()
     }
 
-    fn gather_ips(domains: &cfg::Domains) -> Vec<SocketAddr> {
+    fn gather_ips(targets: &[cfg::Target]) -> Vec<SocketAddr> {
         let mut ips = vec![];
 
-        for domain in domains.list.iter() {
+        for target in targets.iter() {
+            let (domain, mode) = match target {
+                cfg::Target::Ip { address } => {
+                    ips.push(SocketAddr::new(*address, 0));
+                    continue;
+                },
+                cfg::Target::Domain { name, mode } => (name, mode),
+            };
+
             let domain_ips = super::util::domain::http_to_ips(domain);
 
             if domain_ips.is_err() || domain_ips.as_ref().unwrap().as_slice().is_empty() {
@@ -52,7 +141,7 @@ impl Pinger {
             }
             let domain_ips = domain_ips.unwrap();
 
-            match domains.mode {
+            match mode {
                 super::cfg::DomainsMode::FirstIpFromEach => {
                     ips.push(domain_ips.as_slice()[0])
                 },
@@ -66,7 +155,7 @@ impl Pinger {
     }
 
     pub fn update_ips(&mut self) {
-        self.ips = Self::gather_ips(&self.config.domains)
+        self.ips = Self::gather_ips(&self.config.targets)
     }
 
     async fn ping_ip_once(&self, addr: &SocketAddr, buf: winping::Buffer) -> Result<PingOk, PingErr> {
@@ -76,10 +165,12 @@ impl Pinger {
         match answer.result {
             Ok(rtt) => {
                 debug!("{}: rtt={}", addr, rtt);
+                Probe { address: addr.ip(), rtt_ms: Some(rtt), error: None, at: Utc::now() }.record();
                 return Ok(PingOk { buf, rtt })
             },
             Err(err) => {
                 debug!("{}: err={}", addr, err);
+                Probe { address: addr.ip(), rtt_ms: None, error: Some(err.to_string()), at: Utc::now() }.record();
                 return Err(PingErr { buf, err })
             },
         }
@@ -105,11 +196,15 @@ impl Pinger {
                 match self.ping_ip_once(addr, buf).await {
                     Ok(result) => {
                         errors = 0;
+                        set_outage(false, addr);
                         buf = result.buf;
                         tokio::time::sleep(Duration::from_millis(self.config.interval_ms)).await;
                     },
                     Err(err) => {
                         errors += 1;
+                        if errors >= self.config.max_errors {
+                            set_outage(true, addr);
+                        }
                         buf = err.buf;
                         break 'addr;
                     },
@@ -0,0 +1,224 @@
+//! ## Managing profiles stored in Windows
+//!
+//! Exported files are the same XML `netsh wlan export profile`
+//! produces, so they can be used with `netsh` too.
+
+use crate::win::wlan::Interface;
+use crate::win::wlan::network::Profile;
+
+use std::path::{Path, PathBuf};
+use log::{info, warn, error};
+
+
+/// ## Make a profile name safe to use as a file name
+pub fn file_name(profile_name: &str) -> String {
+    let name = profile_name.chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c
+        })
+        .collect::<String>();
+
+    format!("{}.xml", name)
+}
+
+/// ## Print profiles of `iface` in the order of preference
+pub fn list(iface: &Interface) -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    let profiles = match wlan.list_profiles(&iface.guid, false) {
+        Ok(profiles) => profiles,
+        Err(err) => {
+            error!("x PROFILES: could not list profiles of {} ({:?})", iface.description, err);
+            return false
+        }
+    };
+
+    println!("{}", iface.description);
+    for (position, profile) in profiles.iter().enumerate() {
+        println!(
+            "{:>3}  {:<32}  {:<10}  {:<6}  {}{}",
+            position,
+            profile.name,
+            profile.security.auth.to_string(),
+            profile.security.cipher.to_string(),
+            profile.connection.mode,
+            if profile.non_broadcast { ", hidden" } else { "" }
+        );
+    }
+
+    true
+}
+
+/// ## Print the XML of a profile
+/// 
+/// The key is replaced with `Profile::REDACTED`
+/// unless `reveal_key` is set.
+pub fn show(iface: &Interface, name: &str, reveal_key: bool) -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    let mut profile = match wlan.get_profile(&iface.guid, name, reveal_key) {
+        Ok(profile) => profile,
+        Err(err) => {
+            error!("x PROFILES: could not get {} ({:?})", name, err);
+            return false
+        }
+    };
+
+    if !reveal_key {
+        profile.redact_key();
+    }
+
+    println!("{}", profile.genuine_serialize_to_string());
+
+    true
+}
+
+pub fn delete(iface: &Interface, name: &str) -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    match wlan.delete_profile(&iface.guid, name) {
+        Ok(()) => {
+            info!("o PROFILES: deleted {} from {}", name, iface.description);
+            true
+        },
+        Err(err) => {
+            error!("x PROFILES: could not delete {} ({:?})", name, err);
+            false
+        }
+    }
+}
+
+pub fn move_to(iface: &Interface, name: &str, position: u32) -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    match wlan.set_profile_position(&iface.guid, name, position) {
+        Ok(()) => {
+            info!("o PROFILES: moved {} to position {}", name, position);
+            true
+        },
+        Err(err) => {
+            error!("x PROFILES: could not move {} ({:?})", name, err);
+            false
+        }
+    }
+}
+
+/// ## Write every profile of `iface` into `dir`
+/// 
+/// Returns `false` if anything failed, including
+/// profiles that couldn't be read and ones whose
+/// file name is taken by an earlier profile.
+pub async fn export(iface: &Interface, dir: &Path, plaintext_keys: bool) -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    let names = match wlan.list_profile_names(&iface.guid) {
+        Ok(names) => names,
+        Err(err) => {
+            error!("x PROFILES: could not list profiles of {} ({:?})", iface.description, err);
+            return false
+        }
+    };
+
+    if let Err(err) = tokio::fs::create_dir_all(dir).await {
+        error!("x PROFILES: could not create {} ({})", dir.display(), err);
+        return false
+    }
+
+    let mut ok = true;
+    let mut written = vec![];
+
+    for name in names {
+        let profile = match wlan.get_profile(&iface.guid, &name, plaintext_keys) {
+            Ok(profile) => profile,
+            Err(err) => {
+                error!("x PROFILES: could not read {}, skipping it ({:?})", name, err);
+                ok = false;
+                continue
+            }
+        };
+
+        let file_name = file_name(&profile.name);
+        if written.iter().any(|taken: &String| taken.eq_ignore_ascii_case(&file_name)) {
+            error!("x PROFILES: {} would overwrite {} exported earlier, skipping it", name, file_name);
+            ok = false;
+            continue
+        }
+        let path = dir.join(&file_name);
+        written.push(file_name);
+
+        if plaintext_keys && profile.security.key.as_ref().map(|key| key.is_encrypted).unwrap_or(false) {
+            warn!("! PROFILES: key of {} stayed encrypted, run as administrator to decrypt it", profile.name);
+        }
+
+        match tokio::fs::write(&path, profile.genuine_serialize_to_string()).await {
+            Ok(()) => info!("o PROFILES: exported {} to {}", name, path.display()),
+            Err(err) => {
+                error!("x PROFILES: could not write {} ({})", path.display(), err);
+                ok = false
+            }
+        }
+    }
+
+    ok
+}
+
+/// ## Store every `.xml` file in `dir` as a profile of `iface`
+/// 
+/// Files that can't be parsed or stored are reported
+/// and skipped. Returns `false` if there were any.
+pub async fn import(iface: &Interface, dir: &Path, overwrite: bool) -> bool {
+    let wlan = crate::WLAN.get().unwrap();
+
+    let mut paths = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map(|ext| ext.eq_ignore_ascii_case("xml")).unwrap_or(false))
+            .collect::<Vec<PathBuf>>(),
+        Err(err) => {
+            error!("x PROFILES: could not read {} ({})", dir.display(), err);
+            return false
+        }
+    };
+    paths.sort();
+
+    let mut imported = 0;
+    let mut failed = 0;
+
+    for path in paths.iter() {
+        let xml = match tokio::fs::read_to_string(path).await {
+            Ok(xml) => xml,
+            Err(err) => {
+                error!("x PROFILES: could not read {} ({})", path.display(), err);
+                failed += 1;
+                continue
+            }
+        };
+
+        let profile = match Profile::deserialize_str(&xml) {
+            Ok(profile) => profile,
+            Err(err) => {
+                error!("x PROFILES: {} is not a valid profile: {}", path.display(), err);
+                failed += 1;
+                continue
+            }
+        };
+        let name = profile.name.clone();
+
+        match wlan.set_profile(&iface.guid, profile, overwrite) {
+            Ok(()) => {
+                info!("o PROFILES: imported {} from {}", name, path.display());
+                imported += 1
+            },
+            Err(err) => {
+                error!("x PROFILES: could not store {} from {} ({:?})", name, path.display(), err);
+                failed += 1
+            }
+        }
+    }
+
+    info!("o PROFILES: imported {} of {} files into {}", imported, paths.len(), iface.description);
+
+    failed == 0
+}
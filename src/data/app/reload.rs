@@ -0,0 +1,230 @@
+//! ## Hot-reload of the config file
+//!
+//! Periodically checks whether `cfg.json` was modified.
+//! If so, the new config is loaded, validated and
+//! swapped in, and only the parts that changed
+//! are applied:
+//! - `ping`: the pinger is rebuilt
+//! - `interfaces.priority`: interfaces are re-sorted,
+//!   switching to another one if it's now on top
+//! - `wifi.networks`: a network is chosen again if
+//!   the chosen one was changed or removed, or if
+//!   a network now ahead of it is available
+//! - `wifi.roam`, `wifi.schedule`: their loops are
+//!   restarted with the new settings
+//!
+//! Everything else is picked up the next time it's used.
+//! An invalid file is reported the same way as
+//! on startup, and ignored.
+
+use crate::app;
+use crate::app::cfg::{self, Config};
+use crate::app::pinger::{Pinger, PINGER};
+use crate::app::wlan::{event, interface, network};
+use crate::app::wlan::network::event::{autopilot, pinger, roamer, scheduler, waiter};
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use once_cell::sync::Lazy;
+use log::{debug, info, warn, error};
+
+
+pub static HANDLE: Lazy<Arc<RwLock<Option<JoinHandle<()>>>>> = Lazy::new(
+    || Arc::new(RwLock::new(None))
+);
+
+
+fn modified() -> Option<SystemTime> {
+    std::fs::metadata(crate::CFG_PATH.as_path()).ok()?.modified().ok()
+}
+
+fn configs_network(config: &Config, ssid: &str) -> Option<cfg::Network> {
+    config.wifi.networks.iter().find(|net| net.ssid == ssid).cloned()
+}
+
+/// ## SSIDs configured ahead of `ssid`
+fn preceding<'a>(config: &'a Config, ssid: &str) -> Vec<&'a str> {
+    config.wifi.networks.iter()
+        .map(|net| net.ssid.as_str())
+        .take_while(|preceding| *preceding != ssid)
+        .collect()
+}
+
+async fn apply_ping(new: &Config) {
+    let was_pinging = pinger::works().await;
+    if was_pinging {
+        pinger::close_event_loop().await;
+    }
+
+    *PINGER.write().await = Pinger::from_config(new.ping.clone());
+    info!("o CONFIG: ping changed, pinger rebuilt");
+
+    if was_pinging {
+        pinger::spawn_event_loop().await;
+    }
+}
+
+async fn apply_interfaces() {
+    info!("o CONFIG: interfaces.priority changed, re-sorting interfaces");
+
+    let chosen_something_else = interface::CHOSEN.write().await.choose().await.is_some();
+    if !chosen_something_else {
+        return
+    }
+
+    let chosen = interface::CHOSEN.read().await;
+    let guid = chosen.get().unwrap();
+    interface::LIST.read().await.disconnect_all_except(guid).await;
+    std::mem::drop(chosen);
+
+    if app::STATE.read().await.is_dead() {
+        app::STATE.write().await.alive().unwrap();
+    }
+
+    network::restart().await
+}
+
+async fn apply_networks(old: &Config, new: &Config) {
+    if let Err(err) = network::LIST.write().await.update().await {
+        warn!("! CONFIG: could not update the network list ({:?})", err);
+    }
+
+    let chosen = network::CHOSEN.read().await.get().map(|ssid| ssid.to_string());
+    let Some(ssid) = chosen else {
+        info!("o CONFIG: wifi.networks changed, looking for networks");
+        // the scan wakes up whoever is waiting for networks
+        if let Err(err) = interface::CHOSEN.read().await.scan().await {
+            debug!("reload: scan failed ({:?})", err);
+        }
+        return
+    };
+
+    if configs_network(old, &ssid) != configs_network(new, &ssid) {
+        info!("! CONFIG: wifi.networks changed, {} was changed or removed, choosing again", ssid);
+    } else if preceding(old, &ssid) != preceding(new, &ssid) && preferred_available(new, &ssid).await {
+        info!("! CONFIG: wifi.networks changed, a network ahead of {} is available, choosing again", ssid);
+    } else {
+        info!("o CONFIG: wifi.networks changed, {} stays chosen", ssid);
+        return
+    }
+
+    let _ = network::CHOSEN.write().await.unchoose().await;
+    if let Err(err) = interface::CHOSEN.read().await.scan().await {
+        debug!("reload: scan failed ({:?})", err);
+    }
+
+    if network::CHOSEN.write().await.choose(network::SwitchReason::ConfigChange).await.is_some() {
+        return
+    }
+    if let Err(err) = app::STATE.write().await.dead(app::DeadReason::NoNetwork) {
+        debug!("reload: not going dead, the app already is ({:?})", err);
+        return
+    }
+    if !waiter::works().await {
+        waiter::spawn_event_loop().await;
+    }
+}
+
+/// ## Whether a network configured ahead of `ssid` can be chosen
+async fn preferred_available(config: &Config, ssid: &str) -> bool {
+    let preceding = preceding(config, ssid);
+
+    network::LIST.read().await.accessable_ssids().iter()
+        .any(|accessable| preceding.contains(&accessable.as_str()))
+}
+
+/// ## Restart a loop that reads its settings once
+/// 
+/// Only while the network side is running,
+/// since that's what starts and ends these loops.
+async fn apply_roam(new: &Config) {
+    if !autopilot::works().await {
+        return
+    }
+
+    if roamer::works().await {
+        roamer::close_event_loop().await;
+    }
+    if new.wifi.roam.enabled {
+        roamer::spawn_event_loop().await;
+    }
+    info!("o CONFIG: wifi.roam changed, roamer restarted");
+}
+
+async fn apply_schedule() {
+    if !autopilot::works().await {
+        return
+    }
+
+    if scheduler::works().await {
+        scheduler::close_event_loop().await;
+    }
+    scheduler::spawn_event_loop().await;
+    info!("o CONFIG: wifi.schedule changed, scheduler restarted");
+}
+
+/// ## Load the file again and apply what changed
+pub async fn reload() {
+    let new = match Config::load().await {
+        Ok(new) => new,
+        Err(err) => {
+            error!("x CONFIG could not be reloaded, keeping the old one: {}", err);
+            return
+        }
+    };
+
+    if let Err(issues) = new.is_valid() {
+        error!("x CONFIG is invalid, keeping the old one:");
+        cfg::validation::log(&issues);
+        return
+    }
+
+    let old = crate::config();
+    if *old == new {
+        debug!("reload: file was touched, but nothing changed");
+        return
+    }
+
+    *crate::CONFIG.get().unwrap().write().unwrap() = Arc::new(new);
+    let new = crate::config();
+    info!("o CONFIG: RELOADED {}", crate::CFG_PATH.as_path().display());
+
+    if old.ping != new.ping {
+        apply_ping(&new).await;
+    }
+    if old.interfaces.priority != new.interfaces.priority {
+        apply_interfaces().await;
+    }
+    if old.wifi.networks != new.wifi.networks {
+        apply_networks(&old, &new).await;
+    }
+    if old.wifi.roam != new.wifi.roam {
+        apply_roam(&new).await;
+    }
+    if old.wifi.schedule != new.wifi.schedule {
+        apply_schedule().await;
+    }
+}
+
+pub async fn event_loop() {
+    let mut last_modified = modified();
+
+    loop {
+        let interval = crate::config().reload.interval_ms;
+        tokio::time::sleep(Duration::from_millis(interval)).await;
+
+        let current = modified();
+        if current == last_modified {
+            continue
+        }
+        last_modified = current;
+
+        reload().await;
+    }
+}
+
+event::looping::works!(async fn works(HANDLE));
+event::looping::spawner!(async fn spawn_event_loop(HANDLE, event_loop, works));
+event::looping::closer!(async fn close_event_loop(HANDLE));
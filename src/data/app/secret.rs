@@ -0,0 +1,262 @@
+//! ## Secrets that shouldn't live in `cfg.json`
+//!
+//! A password can be written right into the config,
+//! or taken from an environment variable, a file,
+//! or the output of a command. Either way it ends
+//! up as a `Secret`, which never shows itself in
+//! `Debug` or when serialized.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use schemars::{JsonSchema, gen::SchemaGenerator, schema::Schema};
+
+
+pub const REDACTED: &str = "********";
+/// ## How long `passwordCommand` may take
+pub const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+impl Secret {
+    pub fn new(secret: impl ToString) -> Self {
+        Self(secret.to_string())
+    }
+
+    /// ## The actual secret
+    ///
+    /// Don't log it.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+impl JsonSchema for Secret {
+    fn schema_name() -> String {
+        "Secret".to_string()
+    }
+
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+/// ## Where to take a secret from
+#[derive(Debug, Clone)]
+pub enum Source {
+    Literal(Secret),
+    /// ## Environment variable name
+    Env(String),
+    /// ## File, the first line of which is the secret
+    File(PathBuf),
+    /// ## Shell command, the output of which is the secret
+    Command(String),
+}
+impl Source {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Literal(_) => "password",
+            Self::Env(_) => "passwordEnv",
+            Self::File(_) => "passwordFile",
+            Self::Command(_) => "passwordCommand",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// ## More than one source is set
+    Ambiguous(Vec<&'static str>),
+    EnvMissing(String),
+    File(PathBuf, std::io::Error),
+    Command(String, std::io::Error),
+    CommandTimedOut(String),
+    CommandFailed(String, Option<i32>),
+    NotUtf8(&'static str),
+    Empty(&'static str),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ambiguous(kinds) => write!(f, "only one of {} can be set", kinds.join(", ")),
+            Self::EnvMissing(var) => write!(f, "environment variable {} is not set", var),
+            Self::File(path, err) => write!(f, "could not read {} ({})", path.display(), err),
+            Self::Command(command, err) => write!(f, "could not run `{}` ({})", command, err),
+            Self::CommandTimedOut(command) => write!(f, "`{}` timed out after {} secs and was killed", command, COMMAND_TIMEOUT.as_secs()),
+            Self::CommandFailed(command, code) => write!(f, "`{}` exited with code {:?}", command, code),
+            Self::NotUtf8(kind) => write!(f, "{} is not valid UTF-8", kind),
+            Self::Empty(kind) => write!(f, "{} gave an empty secret", kind),
+        }
+    }
+}
+
+/// ## Pick the only source that is set
+pub fn pick(sources: Vec<Option<Source>>) -> Result<Option<Source>, Error> {
+    let mut sources = sources.into_iter().flatten().collect::<Vec<Source>>();
+
+    match sources.len() {
+        0 => Ok(None),
+        1 => Ok(sources.pop()),
+        _ => Err(Error::Ambiguous(sources.iter().map(|source| source.kind()).collect()))
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or("")
+}
+
+/// ## Run `command` and take the first line of its output
+/// 
+/// Killed after `COMMAND_TIMEOUT`, so a hanging
+/// helper doesn't hold up connecting.
+async fn run_command(command: &str) -> Result<String, Error> {
+    #[cfg(windows)]
+    let (shell, flag) = ("cmd", "/C");
+    #[cfg(not(windows))]
+    let (shell, flag) = ("sh", "-c");
+
+    let child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| Error::Command(command.to_string(), err))?;
+
+    // the child is killed if it's dropped on timeout
+    let output = match tokio::time::timeout(COMMAND_TIMEOUT, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|err| Error::Command(command.to_string(), err))?,
+        Err(_) => return Err(Error::CommandTimedOut(command.to_string())),
+    };
+    if !output.status.success() {
+        return Err(Error::CommandFailed(command.to_string(), output.status.code()))
+    }
+
+    let stdout = String::from_utf8(output.stdout).map_err(|_| Error::NotUtf8("passwordCommand"))?;
+    Ok(first_line(&stdout).to_string())
+}
+
+pub async fn resolve(source: &Source) -> Result<Secret, Error> {
+    let secret = match source {
+        Source::Literal(secret) => return Ok(secret.clone()),
+        Source::Env(var) => match std::env::var(var) {
+            Ok(value) => value,
+            Err(std::env::VarError::NotPresent) => return Err(Error::EnvMissing(var.clone())),
+            Err(std::env::VarError::NotUnicode(_)) => return Err(Error::NotUtf8(source.kind())),
+        },
+        Source::File(path) => {
+            let text = std::fs::read_to_string(path).map_err(|err| Error::File(path.clone(), err))?;
+            first_line(&text).to_string()
+        },
+        Source::Command(command) => run_command(command).await?,
+    };
+
+    if secret.is_empty() {
+        return Err(Error::Empty(source.kind()))
+    }
+
+    Ok(Secret(secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_is_redacted() {
+        assert_eq!(format!("{:?}", Secret::new("hunter2")), format!("Secret({})", REDACTED));
+    }
+
+    #[test]
+    fn serializing_is_redacted() {
+        let json = serde_json::to_string(&Secret::new("hunter2")).unwrap();
+        assert_eq!(json, format!("\"{}\"", REDACTED));
+
+        let secret = serde_json::from_str::<Secret>("\"hunter2\"").unwrap();
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[test]
+    fn pick_takes_the_only_source() {
+        assert!(matches!(pick(vec![None, None]), Ok(None)));
+
+        let picked = pick(vec![None, Some(Source::Env("WIFI".to_string()))]).unwrap();
+        assert!(matches!(picked, Some(Source::Env(var)) if var == "WIFI"));
+    }
+
+    #[test]
+    fn pick_rejects_several_sources() {
+        let picked = pick(vec![
+            Some(Source::Literal(Secret::new("hunter2"))),
+            None,
+            Some(Source::Command("echo hunter2".to_string())),
+        ]);
+
+        assert!(matches!(picked, Err(Error::Ambiguous(kinds)) if kinds == vec!["password", "passwordCommand"]));
+    }
+
+    #[tokio::test]
+    async fn env_is_resolved() {
+        std::env::set_var("WIFU_TEST_SECRET_ENV", "hunter2");
+
+        let secret = resolve(&Source::Env("WIFU_TEST_SECRET_ENV".to_string())).await.unwrap();
+        assert_eq!(secret.expose(), "hunter2");
+
+        let missing = resolve(&Source::Env("WIFU_TEST_SECRET_MISSING".to_string())).await;
+        assert!(matches!(missing, Err(Error::EnvMissing(_))));
+    }
+
+    #[tokio::test]
+    async fn file_gives_its_first_line() {
+        let path = std::env::temp_dir().join(format!("wifu-test-secret-{}.txt", std::process::id()));
+        std::fs::write(&path, "hunter2\r\nsecond line\n").unwrap();
+
+        let secret = resolve(&Source::File(path.clone())).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(secret.unwrap().expose(), "hunter2");
+    }
+
+    #[tokio::test]
+    async fn empty_file_is_rejected() {
+        let path = std::env::temp_dir().join(format!("wifu-test-secret-empty-{}.txt", std::process::id()));
+        std::fs::write(&path, "\nhunter2\n").unwrap();
+
+        let secret = resolve(&Source::File(path.clone())).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(secret, Err(Error::Empty("passwordFile"))));
+    }
+
+    #[tokio::test]
+    async fn command_gives_its_first_line() {
+        let secret = resolve(&Source::Command("echo hunter2 && echo second line".to_string())).await;
+        assert_eq!(secret.unwrap().expose(), "hunter2");
+
+        let failed = resolve(&Source::Command("exit 3".to_string())).await;
+        assert!(matches!(failed, Err(Error::CommandFailed(_, Some(3)))));
+    }
+}
@@ -3,7 +3,7 @@ pub mod error;
 pub use reason::DeadReason;
 pub use error::Error;
 
-use crate::app::{interface, network};
+use crate::app::{history, interface, network};
 
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -56,20 +56,8 @@ impl Operator {
         match &self.state {
             State::Alive => {},
             State::Dead(reason) => {
-                match reason {
-                    DeadReason::Uninitialized => {
-                        info!("! DEAD: uninitialized");
-                        info!("? FIX: check that the app's variables are initialized correctly");
-                    },
-                    DeadReason::NoInterface => {
-                        info!("! DEAD: no available wireless interfaces");
-                        info!("? FIX: connect at least one wireless interface (USB, PCIe, virtual, etc.)");
-                    },
-                    DeadReason::NoNetwork => {
-                        info!("! DEAD: could not connect to any available network");
-                        info!("? FIX: check that at least one network defined in config is reachable");
-                    },
-                }
+                info!("! DEAD: {}", reason.message());
+                info!("? FIX: {}", reason.fix());
             }
         }
     }
@@ -96,6 +84,7 @@ impl Operator {
         self.set(State::Dead(reason.clone()));
 
         self.print_message();
+        history::record(history::Event::Dead { reason });
 
         Ok(())
     }
@@ -107,6 +96,7 @@ impl Operator {
 
         self.set(State::Alive);
         info!("o APP ALIVE");
+        history::record(history::Event::Alive);
 
         Ok(())
     }
@@ -1,4 +1,8 @@
-#[derive(Debug, Clone)]
+use serde_derive::{Serialize, Deserialize};
+
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum DeadReason {
     Uninitialized,
     NoInterface,
@@ -16,4 +20,30 @@ impl DeadReason {
     pub fn is_no_network(&self) -> bool {
         if let Self::NoNetwork = *self { true } else { false }
     }
+
+    /// ## Name, as it's serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Uninitialized => "uninitialized",
+            Self::NoInterface => "noInterface",
+            Self::NoNetwork => "noNetwork",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::Uninitialized => "uninitialized",
+            Self::NoInterface => "no available wireless interfaces",
+            Self::NoNetwork => "could not connect to any available network",
+        }
+    }
+
+    /// ## What the user can do about it
+    pub fn fix(&self) -> &'static str {
+        match self {
+            Self::Uninitialized => "check that the app's variables are initialized correctly",
+            Self::NoInterface => "connect at least one wireless interface (USB, PCIe, virtual, etc.)",
+            Self::NoNetwork => "check that at least one network defined in config is reachable",
+        }
+    }
 }
@@ -0,0 +1,305 @@
+//! ## Webhooks
+//!
+//! POSTs JSON to `webhooks.endpoints` when another
+//! network is chosen, and when the app goes dead
+//! or alive. Deliveries are queued and sent one by
+//! one, failed ones are retried with a growing delay
+//! while the others are sent in the meantime.
+//! Since requests go through whatever connection
+//! Windows routes them to, a delivery that failed
+//! during an outage goes out through the network
+//! that was switched to.
+
+use crate::app::cfg::{WebhookEndpoint, WebhookEvent};
+use crate::app::history::{Context, Entry, Event};
+use crate::app::pinger::ProbeStats;
+use crate::app::wlan::event;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde_derive::Serialize;
+use tokio::sync::{Notify, RwLock};
+use tokio::time::Instant;
+use tokio::task::JoinHandle;
+use once_cell::sync::Lazy;
+use log::{debug, info, warn, error};
+
+
+pub static HANDLE: Lazy<Arc<RwLock<Option<JoinHandle<()>>>>> = Lazy::new(
+    || Arc::new(RwLock::new(None))
+);
+static QUEUE: Lazy<Mutex<VecDeque<Delivery>>> = Lazy::new(
+    || Mutex::new(VecDeque::new())
+);
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// ## Deliveries kept while endpoints can't be reached
+const MAX_QUEUED: usize = 100;
+
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfaceInfo {
+    pub guid: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Payload {
+    /// ## `switch`, `dead`, `alive` or `test`
+    pub event: &'static str,
+    pub at: DateTime<Utc>,
+    pub host: String,
+    /// ## One line for humans, shown by Slack and Teams
+    pub text: String,
+    pub old_ssid: Option<String>,
+    pub new_ssid: Option<String>,
+    pub interface: Option<InterfaceInfo>,
+    pub reason: Option<String>,
+    pub probes: ProbeStats,
+}
+impl Payload {
+    /// ## Payload for a journal entry, if it's worth one
+    pub fn from_entry(entry: &Entry, before: &Context, after: &Context) -> Option<(WebhookEvent, Self)> {
+        let host = host();
+
+        let (event, reason, text) = match &entry.event {
+            Event::NetworkChosen { ssid, reason } if before.ssid != after.ssid => {
+                let text = match before.ssid.as_ref() {
+                    Some(old) => format!("{}: switched from {} to {} ({})", host, old, ssid, reason.as_str()),
+                    None => format!("{}: connected to {} ({})", host, ssid, reason.as_str()),
+                };
+                (WebhookEvent::Switch, Some(reason.as_str()), text)
+            },
+            Event::Dead { reason } => {
+                (WebhookEvent::Dead, Some(reason.as_str()), format!("{}: DEAD, {}", host, reason.message()))
+            },
+            Event::Alive => {
+                let text = match after.ssid.as_ref() {
+                    Some(ssid) => format!("{}: alive again on {}", host, ssid),
+                    None => format!("{}: alive again", host),
+                };
+                (WebhookEvent::Alive, None, text)
+            },
+            _ => return None
+        };
+
+        let payload = Self {
+            event: event_name(event),
+            at: entry.at,
+            host,
+            text,
+            old_ssid: before.ssid.clone(),
+            new_ssid: after.ssid.clone(),
+            interface: after.interface.clone().map(|(guid, description)| InterfaceInfo { guid, description }),
+            reason: reason.map(|reason| reason.to_string()),
+            probes: ProbeStats::latest(),
+        };
+
+        Some((event, payload))
+    }
+
+    pub fn test() -> Self {
+        let host = host();
+
+        Self {
+            event: "test",
+            at: Utc::now(),
+            text: format!("{}: this is a test of wifu webhooks", host),
+            host,
+            old_ssid: None,
+            new_ssid: None,
+            interface: None,
+            reason: None,
+            probes: ProbeStats::latest(),
+        }
+    }
+}
+
+struct Delivery {
+    url: String,
+    event: &'static str,
+    payload: serde_json::Value,
+    attempts: u32,
+    /// ## When it's due, later for retries
+    next_attempt_at: Instant,
+}
+
+/// ## Why a delivery failed
+struct Failure {
+    reason: String,
+    /// ## Whether sending it again may help
+    retry: bool,
+}
+
+fn event_name(event: WebhookEvent) -> &'static str {
+    match event {
+        WebhookEvent::Switch => "switch",
+        WebhookEvent::Dead => "dead",
+        WebhookEvent::Alive => "alive",
+    }
+}
+
+/// ## Name of this PC
+fn host() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or("wifu".to_string())
+}
+
+/// ## Queue deliveries of a journal entry
+pub fn notify(entry: &Entry, before: &Context, after: &Context) {
+    let config = crate::config();
+    if config.webhooks.endpoints.is_empty() {
+        return
+    }
+
+    let Some((event, payload)) = Payload::from_entry(entry, before, after) else {
+        return
+    };
+    let payload = serde_json::to_value(&payload).unwrap();
+
+    let mut queue = QUEUE.lock().unwrap();
+    for endpoint in config.webhooks.endpoints.iter().filter(|endpoint| endpoint.events.contains(&event)) {
+        if queue.len() >= MAX_QUEUED {
+            let dropped = queue.pop_front().unwrap();
+            warn!("! WEBHOOK: too many undelivered, dropping {} to {}", dropped.event, dropped.url);
+        }
+
+        queue.push_back(Delivery {
+            url: endpoint.url.clone(),
+            event: event_name(event),
+            payload: payload.clone(),
+            attempts: 0,
+            next_attempt_at: Instant::now()
+        });
+    }
+
+    WAKE.notify_one();
+}
+
+async fn send(client: &reqwest::Client, url: &str, payload: &serde_json::Value) -> Result<(), Failure> {
+    let timeout = Duration::from_millis(crate::config().webhooks.timeout_ms);
+
+    let response = client.post(url)
+        .timeout(timeout)
+        .json(payload)
+        .send()
+        .await
+        .map_err(|err| Failure { reason: err.to_string(), retry: true })?;
+
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(())
+    }
+
+    // the endpoint refused the request itself, the same one won't do better
+    let retry = !status.is_client_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS;
+
+    Err(Failure { reason: format!("HTTP {}", status), retry })
+}
+
+/// ## Delay before the next attempt
+fn backoff(attempts: u32) -> Duration {
+    let config = crate::config();
+    let doubled = config.webhooks.retry_initial_ms.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+
+    Duration::from_millis(doubled.min(config.webhooks.retry_max_ms))
+}
+
+/// ## Send a test payload to each endpoint once
+///
+/// Returns `false` if any of them failed.
+pub async fn test(endpoints: &[WebhookEndpoint]) -> bool {
+    let client = reqwest::Client::new();
+    let payload = serde_json::to_value(Payload::test()).unwrap();
+    let mut ok = true;
+
+    for endpoint in endpoints.iter() {
+        match send(&client, &endpoint.url, &payload).await {
+            Ok(()) => info!("o WEBHOOK: delivered a test to {}", endpoint.url),
+            Err(failure) => {
+                error!("x WEBHOOK: could not deliver a test to {} ({})", endpoint.url, failure.reason);
+                ok = false;
+            }
+        }
+    }
+
+    ok
+}
+
+/// ## Take the delivery that's due first
+///
+/// `Err` with when the next one is due if none
+/// is due yet, `Err(None)` if the queue is empty.
+fn take_due() -> Result<Delivery, Option<Instant>> {
+    let mut queue = QUEUE.lock().unwrap();
+
+    let Some((idx, at)) = queue.iter()
+        .enumerate()
+        .min_by_key(|(_, delivery)| delivery.next_attempt_at)
+        .map(|(idx, delivery)| (idx, delivery.next_attempt_at))
+    else {
+        return Err(None)
+    };
+
+    if at > Instant::now() {
+        return Err(Some(at))
+    }
+
+    Ok(queue.remove(idx).unwrap())
+}
+
+pub async fn event_loop() {
+    let client = reqwest::Client::new();
+
+    loop {
+        let mut delivery = match take_due() {
+            Ok(delivery) => delivery,
+            Err(None) => {
+                WAKE.notified().await;
+                continue
+            },
+            Err(Some(at)) => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(at) => (),
+                    _ = WAKE.notified() => (),
+                }
+                continue
+            }
+        };
+
+        delivery.attempts += 1;
+        match send(&client, &delivery.url, &delivery.payload).await {
+            Ok(()) => debug!("webhooks: delivered {} to {}", delivery.event, delivery.url),
+            Err(failure) if !failure.retry => error!(
+                "x WEBHOOK: {} to {} was refused, not retrying ({})",
+                delivery.event, delivery.url, failure.reason
+            ),
+            Err(failure) if delivery.attempts >= crate::config().webhooks.max_attempts => error!(
+                "x WEBHOOK: giving up on {} to {} after {} attempts ({})",
+                delivery.event, delivery.url, delivery.attempts, failure.reason
+            ),
+            Err(failure) => {
+                let delay = backoff(delivery.attempts);
+                warn!(
+                    "! WEBHOOK: could not deliver {} to {} ({}), retrying in {} ms",
+                    delivery.event, delivery.url, failure.reason, delay.as_millis()
+                );
+
+                delivery.next_attempt_at = Instant::now() + delay;
+                QUEUE.lock().unwrap().push_back(delivery);
+            }
+        }
+    }
+}
+
+event::looping::works!(async fn works(HANDLE));
+event::looping::spawner!(async fn spawn_event_loop(HANDLE, event_loop, works));
+event::looping::closer!(async fn close_event_loop(HANDLE));
@@ -1,8 +1,10 @@
+use crate::app::history;
 use crate::app::interface::LIST;
 use crate::win;
 use crate::win::guid;
 use crate::win::wlan::network::{Profile, Bss};
-use crate::win::wlan::Interface;
+use crate::win::wlan::{Interface, AccessPoint, Bssid};
+use crate::win::wlan::connection;
 
 use log::info;
 use windows::core::GUID;
@@ -51,12 +53,17 @@ impl Operator {
 
     pub async fn get_profile(&self, name: &str) -> win::NativeResult<Profile> {
         let wlan = crate::WLAN.get().unwrap();
-        wlan.get_profile(self.get().unwrap(), name)
+        wlan.get_profile(self.get().unwrap(), name, true)
     }
 
-    pub async fn set_profile(&self, profile: Profile) -> win::NativeResult<()> {
+    pub async fn set_profile(&self, profile: Profile, overwrite: bool) -> win::NativeResult<()> {
         let wlan = crate::WLAN.get().unwrap();
-        wlan.set_profile(self.get().unwrap(), profile)
+        wlan.set_profile(self.get().unwrap(), profile, overwrite)
+    }
+
+    pub async fn set_profile_eap_user_data(&self, name: &str, user_data: &str) -> win::NativeResult<()> {
+        let wlan = crate::WLAN.get().unwrap();
+        wlan.set_profile_eap_user_data(self.get().unwrap(), name, user_data)
     }
 
     pub fn profile_exists(&self, name: &str) -> bool {
@@ -74,11 +81,36 @@ impl Operator {
         }
     }
 
-    pub async fn connect(&self, profile: &str, bss: &Bss) -> win::NativeResult<bool> {
+    pub fn access_points(&self, ssid: &str) -> win::NativeResult<Vec<AccessPoint>> {
         let wlan = crate::WLAN.get().unwrap();
 
         if let Some(chosen) = &self.chosen {
-            wlan.connect(chosen, profile, bss).await
+            wlan.access_points(chosen, Some(ssid))
+        } else {
+            Err(win::NativeError::NotFound)
+        }
+    }
+
+    pub fn current_connection(&self) -> win::NativeResult<Option<connection::Attributes>> {
+        let wlan = crate::WLAN.get().unwrap();
+
+        if let Some(chosen) = &self.chosen {
+            wlan.current_connection(chosen)
+        } else {
+            Err(win::NativeError::NotFound)
+        }
+    }
+
+    pub fn last_fail_reason(&self) -> Option<u32> {
+        let wlan = crate::WLAN.get().unwrap();
+        wlan.last_fail_reason()
+    }
+
+    pub async fn connect(&self, profile: &str, bss: &Bss, bssid: Option<&Bssid>) -> win::NativeResult<bool> {
+        let wlan = crate::WLAN.get().unwrap();
+
+        if let Some(chosen) = &self.chosen {
+            wlan.connect(chosen, profile, bss, bssid).await
         } else {
             Err(win::NativeError::NotFound)
         }
@@ -106,6 +138,7 @@ impl Operator {
             let name = list.get_name_by_guid(&iface.guid).unwrap();
             let guid_string = guid::to_string(&iface.guid);
             info!("o INTERFACE: CHOSE {} (GUID: {})", name, guid_string);
+            history::record(history::Event::InterfaceChosen { guid: guid_string, description: name });
 
             self.get()
         } else {
@@ -113,6 +146,18 @@ impl Operator {
         }
     }
 
+    /// ## Choose a specific interface, bypassing the priority
+    pub fn choose_interface(&mut self, iface: &Interface) {
+        self.set_guid(iface.guid);
+        self.set_name(iface.description.clone());
+
+        info!("o INTERFACE: CHOSE {} (GUID: {})", iface.description, guid::to_string(&iface.guid));
+        history::record(history::Event::InterfaceChosen {
+            guid: guid::to_string(&iface.guid),
+            description: iface.description.clone()
+        });
+    }
+
     pub async fn unchoose(&mut self) -> Result<(), ()> {
         if self.chosen.is_none() {
             return Err(())
@@ -120,6 +165,10 @@ impl Operator {
 
         let guid_string = self.as_string().unwrap();
         info!("x INTERFACE: UNCHOSE {} (GUID: {})", self.name().unwrap(), guid_string);
+        history::record(history::Event::InterfaceUnchosen {
+            guid: guid_string,
+            description: self.name().unwrap().to_string()
+        });
 
         self.chosen = None;
 
@@ -1,4 +1,5 @@
 use crate::app;
+use crate::app::metrics;
 use crate::app::wlan::event;
 use crate::app::wlan::interface::{CHOSEN, LIST};
 use crate::app::wlan::network;
@@ -24,6 +25,7 @@ pub async fn event_loop() {
 
         match notif.code {
             AcmNotifCode::InterfaceArrival => {
+                metrics::interface_arrived();
                 let chosen_something_else = CHOSEN.write().await.choose().await.is_some();
 
                 if chosen_something_else {
@@ -41,6 +43,7 @@ pub async fn event_loop() {
                 }
             },
             AcmNotifCode::InterfaceRemoval => {
+                metrics::interface_removed();
                 network::LIST.write().await.clear();
 
                 let list = LIST.read().await;
@@ -34,7 +34,7 @@ impl Operator {
 
     pub fn get_by_str_guid(&self, guid: &str) -> Option<Interface> {
         self.list.iter()
-            .find(|iface| guid::to_string(&iface.guid) == guid)
+            .find(|iface| guid::to_string(&iface.guid).eq_ignore_ascii_case(guid))
             .map(|iface| iface.clone())
     }
 
@@ -47,7 +47,7 @@ impl Operator {
     }
 
     pub fn contains_str_guid(&self, guid: &str) -> bool {
-        self.list.iter().find(|iface| guid::to_string(&iface.guid) == guid).is_some()
+        self.list.iter().find(|iface| guid::to_string(&iface.guid).eq_ignore_ascii_case(guid)).is_some()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -55,7 +55,7 @@ impl Operator {
     }
 
     pub fn sorted_priority(&self) -> Vec<Interface> {
-        let config = crate::CONFIG.get().unwrap();
+        let config = crate::config();
         let mut prioritized = vec![];
 
         for guid_str in config.interfaces.priority.iter() {
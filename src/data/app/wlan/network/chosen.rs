@@ -1,13 +1,19 @@
-use crate::app::cfg;
-use crate::app::{util::priority, wlan::{interface, network::LIST}};
+use crate::app::{cfg, cli, control, history};
+use crate::app::secret::Secret;
+use crate::app::{metrics, util::priority, wlan::{interface, network::{LIST, SwitchReason, drift, preference}}};
 use crate::win;
-use crate::win::wlan::network::profile::Key;
+use crate::win::wlan::network::{Bss, Profile, profile::{Key, onex, protector}};
+use crate::win::wlan::{Bssid, connection};
 
-use log::{debug, info};
+use std::collections::HashMap;
+
+use log::{debug, info, warn, error};
 
 pub struct Operator {
     choosing: bool,
-    chosen: Option<String>
+    chosen: Option<String>,
+    /// ## Authentication failures in a row, by SSID
+    security_failures: HashMap<String, u32>,
 }
 impl Operator {
     pub fn get(&self) -> Option<&str> {
@@ -30,37 +36,275 @@ impl Operator {
         self.chosen.as_ref().map(|chosen| chosen == ssid).unwrap_or(false)
     }
 
-    pub fn configs_network(&self) -> Option<&cfg::Network> {
-        let config = crate::CONFIG.get().unwrap();
+    pub fn configs_network(&self) -> Option<cfg::Network> {
+        let config = crate::config();
 
         self.chosen.as_ref().map(
-            |chosen| config.wifi.networks.iter().find(|net| &net.ssid == chosen)
+            |chosen| config.wifi.networks.iter().find(|net| &net.ssid == chosen).cloned()
         ).flatten()
     }
 
-    pub async fn connect(&self) -> win::NativeResult<bool> {
+    /// ## Connect to the chosen network, counting the attempt
+    /// 
+    /// In a dry run, nothing is connected so nothing is counted.
+    pub async fn connect(&self, force_provision: bool) -> win::NativeResult<bool> {
+        let ssid = self.get().unwrap();
+        if cli::options().dry_run {
+            return self.try_connect(force_provision).await
+        }
+
+        metrics::connect_attempted(ssid);
+        let result = self.try_connect(force_provision).await;
+        if !matches!(result, Ok(true)) {
+            metrics::connect_failed(ssid);
+        }
+
+        result
+    }
+
+    /// ## Count and journal a switch
+    /// 
+    /// In a dry run it's journaled as `wouldChoose`,
+    /// so reports, webhooks and hooks don't take it
+    /// for a network that was joined.
+    fn switched(ssid: String, reason: SwitchReason) {
+        if cli::options().dry_run {
+            history::record(history::Event::WouldChoose { ssid, reason });
+            return
+        }
+
+        metrics::switched(reason);
+        history::record(history::Event::NetworkChosen { ssid, reason });
+    }
+
+    async fn try_connect(&self, force_provision: bool) -> win::NativeResult<bool> {
         assert!(self.chosen.is_some());
 
+        let cfgs_network = &self.configs_network().unwrap();
+        let Some(password) = Self::password(cfgs_network).await else {
+            return Ok(false)
+        };
+
         let iface = interface::CHOSEN.read().await;
         let list = LIST.read().await;
- 
-        let cfgs_network = self.configs_network().unwrap();
         let live_network = list.get_by_ssid(&cfgs_network.ssid);
+        if live_network.is_none() && cfgs_network.hidden {
+            return Self::connect_hidden(&iface, cfgs_network, password.as_ref(), force_provision).await
+        }
         if live_network.is_none() {
             return Ok(false)
         }
         let live_network = live_network.unwrap();
 
+        let key = password.as_ref().map(|pwd| Key::from_plain(pwd.expose()));
+        let mut profile = match live_network.clone().to_profile(key) {
+            Ok(profile) => profile,
+            Err(err) => {
+                warn!("! NETWORK: can't make a profile for {}: {}", cfgs_network.ssid, err);
+                return Ok(false)
+            }
+        };
+        profile.non_broadcast = cfgs_network.hidden;
+        if let Err(err) = Self::ensure_profile(&iface, cfgs_network, profile, password.as_ref(), true, force_provision).await {
+            warn!("! NETWORK: could not store the profile of {} ({:?})", cfgs_network.ssid, err);
+            return Ok(false)
+        }
+
+        let bssid = cfgs_network.bssid().or_else(|| {
+            let aps = iface.access_points(&cfgs_network.ssid).ok()?;
+            let best = preference::best_access_point(cfgs_network, &aps)?;
+            debug!(
+                "network::connect(): preferring {} ({:?}, {:?}, quality {})",
+                best.bssid, best.band(), best.generation(), best.link_quality
+            );
+            Some(best.bssid)
+        });
+
+        iface.connect(&cfgs_network.ssid, &live_network.bss, bssid.as_ref()).await
+    }
+
+    /// ## Resolve the password of a configured network
+    /// 
+    /// `None` if it can't be resolved, `Some(None)` if there's no password.
+    async fn password(cfgs_network: &cfg::Network) -> Option<Option<Secret>> {
+        match cfgs_network.password().await {
+            Ok(password) => Some(password),
+            Err(err) => {
+                warn!("! NETWORK: can't get the password of {}: {}", cfgs_network.ssid, err);
+                None
+            }
+        }
+    }
+
+    /// ## The profile the config would produce
+    /// 
+    /// Adds EAP settings to `profile` and protects
+    /// its key if `wifi.protectKeys` is on.
+    pub fn expected_profile(cfgs_network: &cfg::Network, profile: Profile) -> Profile {
+        let config = crate::config();
+        let protector = protector::platform();
+
+        let mut expected = profile;
+        if let Some(enterprise) = cfgs_network.enterprise.as_ref() {
+            expected = expected.with_enterprise(enterprise.to_profile_enterprise());
+        }
+        if config.wifi.protect_keys {
+            if let Some(key) = expected.security.key.as_ref() {
+                match key.protect(protector.as_ref()) {
+                    Ok(protected) => expected.security.key = Some(protected),
+                    Err(err) => warn!("! NETWORK: storing the key of {} unprotected: {}", cfgs_network.ssid, err)
+                }
+            }
+        }
+
+        expected
+    }
+
+    /// ## Make sure the stored profile matches the config
+    /// 
+    /// Creates the profile if there's none, otherwise
+    /// rewrites the fields that drifted from `expected`.
+    /// With `force`, the key is rewritten even if
+    /// no drift can be seen (i.e. it's encrypted).
+    /// `Err` if Windows rejected the profile.
+    async fn ensure_profile(
+        iface: &interface::chosen::Operator,
+        cfgs_network: &cfg::Network,
+        expected: Profile,
+        password: Option<&Secret>,
+        compare_security: bool,
+        force: bool
+    ) -> win::NativeResult<()> {
+        let protector = protector::platform();
+        let expected = Self::expected_profile(cfgs_network, expected);
+
         if !iface.profile_exists(&cfgs_network.ssid) {
-            let key = cfgs_network.password.as_ref().map(|pwd| Key::from_plain(pwd));
-            let profile = live_network.clone().to_profile(key);
-            iface.set_profile(profile).await.unwrap();
+            return Self::provision(iface, cfgs_network, expected, password, false).await
+        }
+
+        let stored = match iface.get_profile(&cfgs_network.ssid).await {
+            Ok(stored) => stored,
+            Err(err) => {
+                warn!("! NETWORK: could not read the stored profile of {} ({:?})", cfgs_network.ssid, err);
+                return Ok(())
+            }
+        };
+
+        let drifted = drift::detect(&stored, &expected, compare_security, protector.as_ref());
+        if drifted.is_empty() && !force {
+            return Ok(())
         }
 
-        iface.connect(&cfgs_network.ssid, &live_network.bss).await
+        let fields = if drifted.is_empty() {
+            warn!("! NETWORK: {} keeps failing authentication, re-provisioning its profile", cfgs_network.ssid);
+            vec![drift::Field::KeyMaterial]
+        } else {
+            warn!(
+                "! NETWORK: stored profile of {} drifted from the config ({}), re-provisioning",
+                cfgs_network.ssid, drift::describe(&drifted)
+            );
+            drifted
+        };
+
+        let profile = drift::reconcile(stored, &expected, &fields);
+        Self::provision(iface, cfgs_network, profile, password, true).await
     }
 
-    pub async fn choose(&mut self) -> Option<&str> {
+    /// ## Store the profile for a configured network
+    /// 
+    /// For WPA-Enterprise networks, EAP user credentials
+    /// are stored right after the profile. `Err` if
+    /// Windows rejected either of them.
+    async fn provision(
+        iface: &interface::chosen::Operator,
+        cfgs_network: &cfg::Network,
+        profile: Profile,
+        password: Option<&Secret>,
+        overwrite: bool
+    ) -> win::NativeResult<()> {
+        let name = profile.name.clone();
+        let enterprise = profile.security.enterprise.clone();
+
+        iface.set_profile(profile, overwrite).await?;
+
+        let Some(enterprise) = enterprise else {
+            return Ok(())
+        };
+        let Some(cfgs_enterprise) = cfgs_network.enterprise.as_ref() else {
+            return Ok(())
+        };
+        let (Some(identity), Some(password)) = (&cfgs_enterprise.identity, password) else {
+            return Ok(())
+        };
+        let Some(user_data) = onex::user_credentials_xml(&enterprise, identity, password.expose()) else {
+            return Ok(())
+        };
+
+        iface.set_profile_eap_user_data(&name, &user_data).await.map_err(|err| {
+            error!("x NETWORK: could not store EAP credentials for {} ({:?})", name, err);
+            err
+        })
+    }
+
+    /// ## Directed connect to a network missing from the scan list
+    async fn connect_hidden(
+        iface: &interface::chosen::Operator,
+        cfgs_network: &cfg::Network,
+        password: Option<&Secret>,
+        force_provision: bool
+    ) -> win::NativeResult<bool> {
+        let key = password.map(|pwd| Key::from_plain(pwd.expose()));
+        let security = cfgs_network.security.unwrap_or(if key.is_some() {
+            cfg::HiddenSecurity::Wpa2Personal
+        } else {
+            cfg::HiddenSecurity::Open
+        });
+        let (auth, cipher) = security.to_profile_security();
+        let profile = Profile::from_hidden(cfgs_network.ssid.clone(), key, auth, cipher);
+        // unless it's configured, security of a hidden network is a guess and only the key is compared
+        let compare_security = cfgs_network.security.is_some();
+        if let Err(err) = Self::ensure_profile(iface, cfgs_network, profile, password, compare_security, force_provision).await {
+            warn!("! NETWORK: could not store the profile of {} ({:?})", cfgs_network.ssid, err);
+            return Ok(false)
+        }
+
+        debug!("network::connect(): {} is hidden, trying a directed connect", cfgs_network.ssid);
+
+        iface.connect(&cfgs_network.ssid, &Bss::Infrastructure, cfgs_network.bssid().as_ref()).await
+    }
+
+    /// ## Reconnect to another access point of the chosen network
+    pub async fn roam(&self, bssid: &Bssid) -> win::NativeResult<bool> {
+        assert!(self.chosen.is_some());
+
+        let iface = interface::CHOSEN.read().await;
+        let list = LIST.read().await;
+
+        let cfgs_network = &self.configs_network().unwrap();
+        let live_network = list.get_by_ssid(&cfgs_network.ssid);
+        if live_network.is_none() {
+            return Ok(false)
+        }
+        let live_network = live_network.unwrap();
+
+        iface.connect(&cfgs_network.ssid, &live_network.bss, Some(bssid)).await
+    }
+
+    /// ## Choose the next network, unless the autopilot is paused
+    /// 
+    /// While paused, the chosen network is kept.
+    /// If there's none, one is chosen anyway.
+    pub async fn choose(&mut self, reason: SwitchReason) -> Option<&str> {
+        if control::is_paused() && self.chosen.is_some() {
+            debug!("network::choose(): autopilot is paused, keeping {:?}", self.chosen);
+            return self.get()
+        }
+
+        self.choose_next(reason).await
+    }
+
+    /// ## Switch to the network after the chosen one
+    pub async fn choose_next(&mut self, reason: SwitchReason) -> Option<&str> {
         self.choosing = true;
 
         loop {
@@ -77,21 +321,34 @@ impl Operator {
 
             if current.is_none() {
                 debug!("network::choose(): current is none, returning");
+                self.choosing = false;
                 return None
             }
 
-            self.set(current.unwrap().to_string());
+            let ssid = current.unwrap().to_string();
+            self.set(ssid.clone());
             debug!("network::choose(): set current");
+
+            let reprovision = self.should_reprovision(&ssid);
+            if reprovision {
+                self.security_failures.remove(&ssid);
+            }
     
-            let result = self.connect().await;
+            let result = self.connect(reprovision).await;
             if result.is_err() || result.is_ok() && !result.unwrap() {
                 debug!("network::choose(): connection failed, retrying");
+                self.count_security_failure(&ssid).await;
+                if self.configs_network().map(|net| net.hidden).unwrap_or(false) {
+                    LIST.write().await.hidden_failed(&ssid);
+                }
                 continue
             }
+            self.security_failures.remove(&ssid);
 
             debug!("network::choose(): chosen adapter is {:?}", interface::CHOSEN.read().await.get_interface().await);
 
-            info!("o NETWORK: CHOSE {}", self.get().unwrap());
+            info!("o NETWORK: CHOSE {} ({})", self.get().unwrap(), reason.as_str());
+            Self::switched(ssid, reason);
 
             break
         }
@@ -100,12 +357,52 @@ impl Operator {
         self.get()
     }
 
+    /// ## Connect to a specific configured network, bypassing the priority
+    pub async fn choose_ssid(&mut self, ssid: &str) -> win::NativeResult<bool> {
+        self.set(ssid.to_string());
+
+        let result = self.connect(false).await;
+        if matches!(result, Ok(true)) {
+            Self::switched(ssid.to_string(), SwitchReason::Manual);
+        } else {
+            self.chosen = None;
+        }
+
+        result
+    }
+
+    /// ## Count failed attempts that were the security handshake
+    async fn count_security_failure(&mut self, ssid: &str) {
+        let reason = interface::CHOSEN.read().await.last_fail_reason();
+        if !reason.map(connection::is_security_failure).unwrap_or(false) {
+            return
+        }
+
+        let failures = self.security_failures.entry(ssid.to_string()).or_insert(0);
+        *failures += 1;
+        debug!("network::choose(): {} failed authentication ({} in a row, reason {:?})", ssid, failures, reason);
+    }
+
+    /// ## Authentication failures in a row, by SSID
+    pub fn security_failures(&self) -> &HashMap<String, u32> {
+        &self.security_failures
+    }
+
+    fn should_reprovision(&self, ssid: &str) -> bool {
+        let after = crate::config().wifi.connect.reprovision_after_failures;
+
+        after != 0 && self.security_failures.get(ssid).map(|failures| *failures >= after).unwrap_or(false)
+    }
+
     pub async fn unchoose(&mut self) -> Result<(), ()> {
         if self.chosen.is_none() {
             return Err(())
         }
 
         info!("x NETWORK: UNCHOSE {}", self.get().unwrap());
+        if !cli::options().dry_run {
+            history::record(history::Event::NetworkUnchosen { ssid: self.get().unwrap().to_string() });
+        }
 
         self.chosen = None;
 
@@ -114,6 +411,6 @@ impl Operator {
 }
 impl Default for Operator {
     fn default() -> Self {
-        Self { chosen: None, choosing: false }
+        Self { chosen: None, choosing: false, security_failures: HashMap::new() }
     }
 }
\ No newline at end of file
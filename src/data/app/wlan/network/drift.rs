@@ -0,0 +1,171 @@
+//! ## Differences between stored profiles and the config
+//!
+//! Windows keeps a profile once it's created, so changing
+//! the password in the config alone does nothing. Stored
+//! profiles are compared against what the config would
+//! produce, and rewritten when they don't match.
+
+use crate::win::wlan::network::Profile;
+use crate::win::wlan::network::profile::KeyProtector;
+
+use std::fmt;
+use log::debug;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    KeyMaterial,
+    Authentication,
+    Encryption,
+    /// ## 802.1X settings (EAP method, servers, CAs...)
+    Enterprise,
+}
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KeyMaterial => write!(f, "keyMaterial"),
+            Self::Authentication => write!(f, "authentication"),
+            Self::Encryption => write!(f, "encryption"),
+            Self::Enterprise => write!(f, "OneX"),
+        }
+    }
+}
+
+/// ## Fields of `stored` that differ from `expected`
+///
+/// With `compare_security` off, only the key is compared.
+/// That's for hidden networks, whose security is a guess.
+///
+/// Protected keys are compared by their plaintext.
+/// If that can't be revealed, the key is assumed to be fine.
+pub fn detect(
+    stored: &Profile,
+    expected: &Profile,
+    compare_security: bool,
+    protector: &dyn KeyProtector
+) -> Vec<Field> {
+    let mut drifted = vec![];
+
+    if compare_security {
+        if stored.security.auth.to_string() != expected.security.auth.to_string() {
+            drifted.push(Field::Authentication)
+        }
+        if stored.security.cipher.to_string() != expected.security.cipher.to_string() {
+            drifted.push(Field::Encryption)
+        }
+    }
+
+    if stored.security.enterprise != expected.security.enterprise {
+        drifted.push(Field::Enterprise)
+    }
+
+    let key_drifted = match (&stored.security.key, &expected.security.key) {
+        (Some(stored_key), Some(expected_key)) => match stored_key.matches(expected_key, protector) {
+            Ok(matches) => !matches,
+            Err(err) => {
+                debug!("drift::detect(): can't compare the key of {}: {}", stored.name, err);
+                false
+            }
+        },
+        (None, None) => false,
+        _ => true
+    };
+    if key_drifted {
+        drifted.push(Field::KeyMaterial)
+    }
+
+    drifted
+}
+
+/// ## Take drifted fields from `expected`, keeping everything else of `stored`
+/// 
+/// 802.1X settings only make sense together with the
+/// authentication and encryption around them, so if
+/// either profile is an enterprise one, the whole
+/// security section is taken from `expected`.
+pub fn reconcile(mut stored: Profile, expected: &Profile, drifted: &[Field]) -> Profile {
+    if drifted.is_empty() {
+        return stored
+    }
+
+    if expected.is_enterprise() || drifted.contains(&Field::Enterprise) {
+        stored.security = expected.security.clone();
+        return stored
+    }
+
+    for field in drifted {
+        match field {
+            Field::KeyMaterial => stored.security.key = expected.security.key.clone(),
+            Field::Authentication => stored.security.auth = expected.security.auth.clone(),
+            Field::Encryption => stored.security.cipher = expected.security.cipher.clone(),
+            Field::Enterprise => unreachable!(),
+        }
+    }
+
+    stored
+}
+
+pub fn describe(drifted: &[Field]) -> String {
+    drifted.iter()
+        .map(|field| field.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win::wlan::network::profile::{raw, protector, Key, Enterprise, EapMethod};
+
+    fn personal(password: &str) -> Profile {
+        Profile::from_hidden(
+            "Office".to_string(),
+            Some(Key::from_plain(password)),
+            raw::Authentication::WPA2PSK,
+            raw::Encryption::AES
+        )
+    }
+
+    fn enterprise(server_names: &str) -> Profile {
+        personal("unused").with_enterprise(Enterprise {
+            method: EapMethod::PeapMsChapV2,
+            server_names: Some(server_names.to_string()),
+            ca_thumbprints: vec![],
+            certificate_thumbprint: None,
+            anonymous_identity: None,
+        })
+    }
+
+    #[test]
+    fn enterprise_settings_drift() {
+        let drifted = detect(&enterprise("radius.corp"), &enterprise("nps.corp"), true, &protector::Passthrough);
+        assert_eq!(drifted, vec![Field::Enterprise]);
+
+        let drifted = detect(&enterprise("radius.corp"), &enterprise("radius.corp"), true, &protector::Passthrough);
+        assert!(drifted.is_empty());
+    }
+
+    #[test]
+    fn reconciling_into_enterprise_drops_the_key() {
+        let stored = personal("amogUSSR");
+        let expected = enterprise("radius.corp");
+        let drifted = detect(&stored, &expected, true, &protector::Passthrough);
+        assert!(drifted.contains(&Field::Enterprise));
+
+        let reconciled = reconcile(stored, &expected, &drifted);
+        assert!(reconciled.security.key.is_none());
+        assert_eq!(reconciled.security.enterprise, expected.security.enterprise);
+        assert_eq!(reconciled.security.auth.to_string(), expected.security.auth.to_string());
+    }
+
+    #[test]
+    fn personal_reconcile_takes_only_drifted_fields() {
+        let stored = personal("amogUSSR");
+        let expected = personal("amogus");
+        let drifted = detect(&stored, &expected, true, &protector::Passthrough);
+        assert_eq!(drifted, vec![Field::KeyMaterial]);
+
+        let reconciled = reconcile(stored, &expected, &drifted);
+        assert_eq!(reconciled.security.key, expected.security.key);
+    }
+}
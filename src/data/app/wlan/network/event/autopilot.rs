@@ -1,7 +1,7 @@
 use crate::app;
 use crate::app::wlan::event;
 use crate::app::wlan::interface;
-use crate::app::wlan::network::{LIST, CHOSEN, event::{pinger, waiter}};
+use crate::app::wlan::network::{LIST, CHOSEN, SwitchReason, event::{pinger, waiter}};
 use crate::win::wlan::acm::notification::Code as AcmNotifCode;
 
 use std::sync::Arc;
@@ -52,7 +52,13 @@ pub async fn event_loop() {
                     }
 
                     debug!("network::autopilot calls choose");
-                    CHOSEN.write().await.choose().await.unwrap();
+                    if CHOSEN.write().await.choose(SwitchReason::NetworkAppeared).await.is_none() {
+                        debug!("network::autopilot: nothing could be connected to, still dead");
+                        if !waiter::works().await {
+                            waiter::spawn_event_loop().await;
+                        }
+                        continue
+                    }
                     app::STATE.write().await.alive().unwrap();
                     pinger::spawn_event_loop().await;
                 } else if !cfg_networks_available && !CHOSEN.read().await.is_chosen() && app_state.can_die() {
@@ -65,14 +71,28 @@ pub async fn event_loop() {
                     debug!("network::autopilot: cfg_networks_available && app_state.is_dead() && app_state.get_dead_reason().unwrap().is_uninitialized()");
                     std::mem::drop(app_state);
                     debug!("network autopilot calls choose");
-                    CHOSEN.write().await.choose().await.unwrap();
+                    if CHOSEN.write().await.choose(SwitchReason::Startup).await.is_none() {
+                        debug!("network::autopilot: nothing could be connected to on startup");
+                        app::STATE.write().await.dead(app::DeadReason::NoNetwork).unwrap();
+                        if !waiter::works().await {
+                            waiter::spawn_event_loop().await;
+                        }
+                        continue
+                    }
                     app::STATE.write().await.alive().unwrap();
                     pinger::spawn_event_loop().await;
                 } else if cfg_networks_available && app_state.is_alive() && !CHOSEN.read().await.is_chosen() {
                     debug!("network::autopilot: cfg_networks_available && app_state.is_alive() && !CHOSEN.read().await.is_chosen()");
                     std::mem::drop(app_state);
                     debug!("network autopilot calls choose");
-                    CHOSEN.write().await.choose().await.unwrap();
+                    if CHOSEN.write().await.choose(SwitchReason::NetworkAppeared).await.is_none() {
+                        debug!("network::autopilot: nothing could be connected to");
+                        app::STATE.write().await.dead(app::DeadReason::NoNetwork).unwrap();
+                        if !waiter::works().await {
+                            waiter::spawn_event_loop().await;
+                        }
+                        continue
+                    }
                     pinger::spawn_event_loop().await;
                 }
             },
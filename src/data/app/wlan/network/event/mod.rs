@@ -1,3 +1,5 @@
 pub mod autopilot;
 pub mod pinger;
 pub mod waiter;
+pub mod roamer;
+pub mod scheduler;
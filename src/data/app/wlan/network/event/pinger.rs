@@ -2,7 +2,7 @@ use crate::app;
 use crate::app::pinger::PINGER;
 use crate::app::wlan::event;
 use crate::app::wlan::interface;
-use crate::app::wlan::network::{LIST, CHOSEN};
+use crate::app::wlan::network::{LIST, CHOSEN, SwitchReason};
 use crate::app::wlan::network::event::waiter;
 
 use std::sync::Arc;
@@ -21,7 +21,7 @@ pub async fn event_loop() {
         if PINGER.read().await.has_no_ips() {
             interface::CHOSEN.read().await.scan().await.unwrap();
 
-            if CHOSEN.write().await.choose().await.is_none() {
+            if CHOSEN.write().await.choose(SwitchReason::Startup).await.is_none() {
                 return close_event_loop().await
             }
 
@@ -32,7 +32,7 @@ pub async fn event_loop() {
 
         interface::CHOSEN.read().await.scan().await.unwrap();
 
-        if CHOSEN.write().await.choose().await.is_none() {
+        if CHOSEN.write().await.choose(SwitchReason::ProbeFailure).await.is_none() {
             app::STATE.write().await.dead(app::DeadReason::NoNetwork).unwrap();
             waiter::spawn_event_loop().await;
             return close_event_loop().await
@@ -0,0 +1,117 @@
+//! ## Roaming between access points of one SSID
+//! 
+//! - Periodically checks the link quality of the
+//!   access point the chosen interface is associated with
+//! - If it degrades below `wifi.roam.minQuality`,
+//!   picks the access point of the same SSID the same
+//!   way connecting does (see `preference::best_access_point`)
+//! - Reconnects to it if it's not the current one and
+//!   the gain is at least `wifi.roam.minQualityGain`
+//! 
+//! Networks with a pinned `bssid` in config never roam,
+//! and nothing roams while the autopilot is paused.
+
+use crate::app::control;
+use crate::app::wlan::event;
+use crate::app::wlan::interface;
+use crate::app::wlan::network::{CHOSEN, preference};
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use once_cell::sync::Lazy;
+use log::{debug, info, warn};
+
+
+pub static HANDLE: Lazy<Arc<RwLock<Option<JoinHandle<()>>>>> = Lazy::new(
+    || Arc::new(RwLock::new(None))
+);
+
+
+async fn roam_once() {
+    let config = crate::config();
+    let config = &config.wifi.roam;
+    let chosen = CHOSEN.read().await;
+
+    let Some(cfgs_network) = chosen.configs_network() else {
+        return
+    };
+    if cfgs_network.bssid.is_some() {
+        return
+    }
+
+    let iface = interface::CHOSEN.read().await;
+    let current = match iface.current_connection() {
+        Ok(Some(current)) => current,
+        Ok(None) => return,
+        Err(err) => {
+            debug!("network::roamer: can't query current connection ({:?})", err);
+            return
+        }
+    };
+    if current.ssid != cfgs_network.ssid {
+        return
+    }
+
+    let access_points = match iface.access_points(&cfgs_network.ssid) {
+        Ok(access_points) => access_points,
+        Err(err) => {
+            debug!("network::roamer: can't list access points ({:?})", err);
+            return
+        }
+    };
+    std::mem::drop(iface);
+
+    let current_quality = access_points.iter()
+        .find(|ap| ap.bssid == current.bssid)
+        .map(|ap| ap.link_quality)
+        .unwrap_or(current.signal_quality);
+
+    if current_quality >= config.min_quality {
+        return
+    }
+
+    // same pick as on connect, so the two don't undo each other
+    let Some(best) = preference::best_access_point(&cfgs_network, &access_points) else {
+        return
+    };
+    if best.bssid == current.bssid {
+        return
+    }
+
+    if best.link_quality < current_quality + config.min_quality_gain {
+        return
+    }
+
+    info!(
+        "o NETWORK: ROAMING {} from {} (quality {}) to {} (quality {})",
+        cfgs_network.ssid,
+        current.bssid,
+        current_quality,
+        best.bssid,
+        best.link_quality
+    );
+
+    match chosen.roam(&best.bssid).await {
+        Ok(true) => (),
+        Ok(false) => warn!("! NETWORK: could not roam to {}", best.bssid),
+        Err(err) => warn!("! NETWORK: could not roam to {} ({:?})", best.bssid, err),
+    }
+}
+
+pub async fn event_loop() {
+    let interval = crate::config().wifi.roam.interval_ms;
+
+    loop {
+        tokio::time::sleep(Duration::from_millis(interval)).await;
+        if control::is_paused() {
+            continue
+        }
+        roam_once().await;
+    }
+}
+
+event::looping::works!(async fn works(HANDLE));
+event::looping::spawner!(async fn spawn_event_loop(HANDLE, event_loop, works));
+event::looping::closer!(async fn close_event_loop(HANDLE));
@@ -0,0 +1,68 @@
+//! ## Switching away when a schedule window closes
+//! 
+//! Periodically checks whether the chosen network
+//! is still inside its schedule. If it isn't,
+//! chooses another network the same way
+//! a failed ping does. Nothing is switched while
+//! the autopilot is paused.
+
+use crate::app;
+use crate::app::control;
+use crate::app::wlan::event;
+use crate::app::wlan::interface;
+use crate::app::wlan::network::{CHOSEN, SwitchReason, schedule};
+use crate::app::wlan::network::event::waiter;
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use once_cell::sync::Lazy;
+use log::{debug, info, warn};
+
+
+pub static HANDLE: Lazy<Arc<RwLock<Option<JoinHandle<()>>>>> = Lazy::new(
+    || Arc::new(RwLock::new(None))
+);
+
+
+pub async fn event_loop() {
+    let interval = crate::config().wifi.schedule.check_interval_ms;
+
+    loop {
+        tokio::time::sleep(Duration::from_millis(interval)).await;
+        if control::is_paused() {
+            continue
+        }
+
+        let chosen = CHOSEN.read().await;
+        let Some(cfgs_network) = chosen.configs_network() else {
+            continue
+        };
+        if schedule::is_allowed(&cfgs_network) {
+            continue
+        }
+
+        info!("! NETWORK: SCHEDULE WINDOW CLOSED for {}, switching", cfgs_network.ssid);
+        std::mem::drop(chosen);
+
+        if let Err(err) = interface::CHOSEN.read().await.scan().await {
+            warn!("! NETWORK: scan failed, choosing from the last list ({:?})", err);
+        }
+
+        if CHOSEN.write().await.choose(SwitchReason::Schedule).await.is_some() {
+            continue
+        }
+        if let Err(err) = app::STATE.write().await.dead(app::DeadReason::NoNetwork) {
+            debug!("network::scheduler: not going dead, the app already is ({:?})", err);
+            continue
+        }
+        if !waiter::works().await {
+            waiter::spawn_event_loop().await;
+        }
+    }
+}
+
+event::looping::works!(async fn works(HANDLE));
+event::looping::spawner!(async fn spawn_event_loop(HANDLE, event_loop, works));
+event::looping::closer!(async fn close_event_loop(HANDLE));
@@ -1,11 +1,16 @@
 use crate::app::cfg;
-use crate::app::wlan::network::UpdateError;
+use crate::app::wlan::network::{UpdateError, preference, schedule};
 use crate::app::wlan::interface;
 use crate::win::wlan::Network;
 
+use log::{debug, warn};
+
 
 pub struct Operator {
-    list: Vec<Network>
+    list: Vec<Network>,
+    reported_unsupported: Vec<String>,
+    /// ## Hidden networks that failed since the last scan
+    failed_hidden: Vec<String>,
 }
 impl Operator {
     pub fn as_slice(&self) -> &[Network] {
@@ -27,12 +32,16 @@ impl Operator {
     pub fn map_with_config(&self) -> Vec<(cfg::Network, Network)> {
         let mut v = vec![];
     
-        for net in crate::CONFIG.get().unwrap().wifi.networks.iter() {
+        for net in crate::config().wifi.networks.iter() {
             let corresponding_result = self.list.iter()
                 .find(|live_net| live_net.ssid == net.ssid)
                 .clone();
             if corresponding_result.is_none() { continue }
             let corresponding = corresponding_result.unwrap();
+
+            if Self::exclusion(net, corresponding).is_some() {
+                continue
+            }
     
             v.push((net.clone(), corresponding.clone()));
         }
@@ -40,18 +49,111 @@ impl Operator {
         v
     }
 
+    /// ## Check that a profile can be made from the config
+    fn check_supported(cfg_net: &cfg::Network, live_net: &Network) -> Result<(), String> {
+        live_net.check_supported().map_err(|err| err.to_string())?;
+
+        if live_net.security.auth.is_enterprise() && cfg_net.enterprise.is_none() {
+            return Err("it's WPA-Enterprise, but has no `enterprise` settings".to_string())
+        }
+
+        Ok(())
+    }
+
+    /// ## Why a visible configured network is left out of selection
+    pub fn exclusion(cfg_net: &cfg::Network, live_net: &Network) -> Option<String> {
+        if let Err(err) = Self::check_supported(cfg_net, live_net) {
+            return Some(err)
+        }
+
+        if !preference::network_meets_minimum(cfg_net, live_net) {
+            return Some("older than the minimum generation".to_string())
+        }
+
+        if !schedule::is_allowed(cfg_net) {
+            return Some("outside its schedule".to_string())
+        }
+
+        None
+    }
+
+    /// ## Visible configured networks left out of selection, with the reason
+    pub fn excluded(&self) -> Vec<(String, String)> {
+        crate::config().wifi.networks.iter()
+            .filter_map(|cfg_net| {
+                let live_net = self.get_by_ssid(&cfg_net.ssid)?;
+                Self::exclusion(cfg_net, live_net).map(|reason| (cfg_net.ssid.clone(), reason))
+            })
+            .collect()
+    }
+
     pub fn cfg_networks_available(&self) -> bool {
-        !self.map_with_config().is_empty()
+        !self.accessable_ssids().is_empty()
     }
 
+    /// ## SSIDs worth trying to connect to, in config order
+    /// 
+    /// Hidden networks are included since they don't
+    /// show up in the scan list, unless a directed
+    /// connect to them failed since the last scan.
     pub fn accessable_ssids(&self) -> Vec<String> {
-        self.map_with_config().iter().map(
-            |(cfg_net, _live_net)| cfg_net.ssid.clone()
-        ).collect::<Vec<String>>()
+        let mapped = self.map_with_config();
+
+        crate::config().wifi.networks.iter()
+            .filter(|net| self.is_hidden_candidate(net) || mapped.iter().any(|(cfg_net, _)| cfg_net.ssid == net.ssid))
+            .map(|net| net.ssid.clone())
+            .collect::<Vec<String>>()
+    }
+
+    fn is_hidden_candidate(&self, cfg_net: &cfg::Network) -> bool {
+        cfg_net.hidden
+            && schedule::is_allowed(cfg_net)
+            && !self.failed_hidden.contains(&cfg_net.ssid)
+    }
+
+    /// ## Leave out a hidden network until the next scan
+    /// 
+    /// Only if it's missing from the scan list, i.e. the
+    /// failed connect was a directed one.
+    pub fn hidden_failed(&mut self, ssid: &str) {
+        if self.get_by_ssid(ssid).is_some() || self.failed_hidden.iter().any(|failed| failed == ssid) {
+            return
+        }
+
+        debug!("network::list: {} is hidden and failed, skipping it until the next scan", ssid);
+        self.failed_hidden.push(ssid.to_string());
+    }
+
+    /// ## Log configured networks that can't be connected to
+    /// 
+    /// Each SSID is only reported once, until it
+    /// becomes supported again (i.e. the modem
+    /// switches its security mode).
+    fn report_unsupported(&mut self) {
+        let config = crate::config();
+
+        for cfg_net in config.wifi.networks.iter() {
+            let Some(live_net) = self.get_by_ssid(&cfg_net.ssid) else {
+                continue
+            };
+            let is_reported = self.reported_unsupported.contains(&cfg_net.ssid);
+
+            match Self::check_supported(cfg_net, live_net) {
+                Err(err) if !is_reported => {
+                    warn!("! NETWORK: {} is excluded from selection: {}", cfg_net.ssid, err);
+                    self.reported_unsupported.push(cfg_net.ssid.clone());
+                },
+                Ok(()) if is_reported => {
+                    self.reported_unsupported.retain(|ssid| ssid != &cfg_net.ssid);
+                },
+                _ => ()
+            }
+        }
     }
 
     pub fn clear(&mut self) {
         self.list = vec![];
+        self.failed_hidden = vec![];
     }
 
     pub async fn update(&mut self) -> Result<(), UpdateError> {
@@ -69,12 +171,14 @@ impl Operator {
         }
 
         self.list = networks.unwrap();
+        self.failed_hidden = vec![];
+        self.report_unsupported();
     
         Ok(())
     }
 }
 impl Default for Operator {
     fn default() -> Self {
-        Self { list: vec![] }
+        Self { list: vec![], reported_unsupported: vec![], failed_hidden: vec![] }
     }
 }
\ No newline at end of file
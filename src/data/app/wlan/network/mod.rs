@@ -2,7 +2,12 @@ pub mod event;
 pub mod error;
 pub mod list;
 pub mod chosen;
+pub mod preference;
+pub mod schedule;
+pub mod drift;
+pub mod reason;
 pub use error::UpdateError;
+pub use reason::SwitchReason;
 
 use crate::app::wlan::interface;
 
@@ -22,6 +27,12 @@ pub static CHOSEN: Lazy<Arc<RwLock<chosen::Operator>>> = Lazy::new(
 pub async fn start_necessary() {
     debug!("network::start_necessary()");
     event::autopilot::spawn_event_loop().await;
+
+    if crate::config().wifi.roam.enabled {
+        event::roamer::spawn_event_loop().await;
+    }
+
+    event::scheduler::spawn_event_loop().await;
 }
 
 pub async fn start() {
@@ -52,6 +63,14 @@ pub async fn end() {
         event::waiter::close_event_loop().await;
     }
 
+    if event::roamer::works().await {
+        event::roamer::close_event_loop().await;
+    }
+
+    if event::scheduler::works().await {
+        event::scheduler::close_event_loop().await;
+    }
+
     if CHOSEN.read().await.is_chosen() {
         CHOSEN.write().await.unchoose().await.unwrap()
     }
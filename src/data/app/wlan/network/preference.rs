@@ -0,0 +1,71 @@
+//! ## Band and PHY preference
+//! 
+//! Decides which access point of a configured network
+//! to associate with, based on `wifi.preference`
+//! and the network's own `minGeneration`.
+
+use crate::app::cfg;
+use crate::win::wlan::{AccessPoint, Network};
+use crate::win::wlan::network::phy::Generation;
+
+
+pub fn min_generation(cfg_net: &cfg::Network) -> Option<Generation> {
+    let config = crate::config();
+    cfg_net.min_generation.or(config.wifi.preference.min_generation)
+}
+
+/// ## Check the generation against the minimum
+/// 
+/// Unknown generations (i.e. IHV-specific PHYs)
+/// are let through, since we can't judge them.
+pub fn meets_minimum(generation: Option<Generation>, min: Option<Generation>) -> bool {
+    match (generation, min) {
+        (Some(generation), Some(min)) => generation >= min,
+        _ => true
+    }
+}
+
+pub fn network_meets_minimum(cfg_net: &cfg::Network, live_net: &Network) -> bool {
+    meets_minimum(live_net.best_generation(), min_generation(cfg_net))
+}
+
+pub fn access_point_meets_minimum(cfg_net: &cfg::Network, ap: &AccessPoint) -> bool {
+    meets_minimum(ap.generation(), min_generation(cfg_net))
+}
+
+fn band_rank(ap: &AccessPoint) -> usize {
+    let config = crate::config();
+    let bands = &config.wifi.preference.bands;
+
+    ap.band()
+        .map(|band| bands.iter().position(|preferred| preferred == &band))
+        .flatten()
+        .unwrap_or(bands.len())
+}
+
+/// ## Choose the most preferred access point
+/// 
+/// Access points below `wifi.preference.minQuality`
+/// always lose to the ones above it. Among the rest,
+/// the band order from `wifi.preference.bands` wins,
+/// then the newer 802.11 generation, then the link quality.
+pub fn best_access_point<'a>(
+    cfg_net: &cfg::Network,
+    aps: &'a [AccessPoint]
+) -> Option<&'a AccessPoint> {
+    let config = crate::config();
+    let preference = &config.wifi.preference;
+
+    aps.iter()
+        .filter(|ap| access_point_meets_minimum(cfg_net, ap))
+        .min_by_key(|ap| (
+            ap.link_quality < preference.min_quality,
+            band_rank(ap),
+            if preference.prefer_newer_generation {
+                std::cmp::Reverse(ap.generation())
+            } else {
+                std::cmp::Reverse(None)
+            },
+            std::cmp::Reverse(ap.link_quality),
+        ))
+}
@@ -0,0 +1,32 @@
+use serde_derive::{Serialize, Deserialize};
+
+
+/// ## Why a network was chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SwitchReason {
+    /// ## First network after the app or an interface started
+    Startup,
+    /// ## A configured network showed up after there were none
+    NetworkAppeared,
+    /// ## Pings failed `ping.maxErrors` times in a row
+    ProbeFailure,
+    /// ## The schedule window of the chosen network closed
+    Schedule,
+    /// ## The chosen network was changed or removed in the config
+    ConfigChange,
+    /// ## Asked for through the API or `wifu connect`
+    Manual,
+}
+impl SwitchReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Startup => "startup",
+            Self::NetworkAppeared => "networkAppeared",
+            Self::ProbeFailure => "probeFailure",
+            Self::Schedule => "schedule",
+            Self::ConfigChange => "configChange",
+            Self::Manual => "manual",
+        }
+    }
+}
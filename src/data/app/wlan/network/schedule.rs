@@ -0,0 +1,129 @@
+//! ## Time-of-day and weekday rules
+//! 
+//! A network with a `schedule` in config is only
+//! considered inside one of its windows. Networks
+//! without a schedule are always allowed.
+
+use crate::app::cfg;
+
+use chrono::{DateTime, Datelike, NaiveTime, Utc};
+use chrono_tz::Tz;
+use log::debug;
+
+
+pub fn parse_time(string: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(string, "%H:%M").ok()
+}
+
+/// ## Check a single window
+/// 
+/// If `to` is earlier than `from`, the window wraps
+/// around midnight (i.e. `22:00` - `06:00`), and
+/// `days` refer to the day the window opens on.
+pub fn window_contains(
+    window: &cfg::ScheduleWindow,
+    weekday: chrono::Weekday,
+    time: NaiveTime
+) -> bool {
+    let (Some(from), Some(to)) = (parse_time(&window.from), parse_time(&window.to)) else {
+        debug!("network::schedule: invalid window {:?}, ignoring", window);
+        return false
+    };
+
+    let day_allowed = |day: chrono::Weekday| {
+        window.days.is_empty() || window.days.contains(&cfg::Weekday::from_chrono(day))
+    };
+
+    if from <= to {
+        day_allowed(weekday) && time >= from && time < to
+    } else {
+        (day_allowed(weekday) && time >= from)
+        || (day_allowed(weekday.pred()) && time < to)
+    }
+}
+
+pub fn schedule_allows(schedule: &cfg::Schedule) -> bool {
+    schedule_allows_at(schedule, Utc::now())
+}
+
+/// ## Check the schedule at `now`, in its own timezone
+pub fn schedule_allows_at(schedule: &cfg::Schedule, now: DateTime<Utc>) -> bool {
+    let (weekday, time) = match schedule.timezone.as_ref().map(|tz| tz.parse::<Tz>()) {
+        Some(Ok(tz)) => {
+            let local = now.with_timezone(&tz);
+            (local.weekday(), local.time())
+        },
+        Some(Err(err)) => {
+            debug!("network::schedule: invalid timezone ({}), using local", err);
+            let local = now.with_timezone(&chrono::Local);
+            (local.weekday(), local.time())
+        },
+        None => {
+            let local = now.with_timezone(&chrono::Local);
+            (local.weekday(), local.time())
+        }
+    };
+
+    schedule.windows.iter().any(|window| window_contains(window, weekday, time))
+}
+
+pub fn is_allowed(cfg_net: &cfg::Network) -> bool {
+    cfg_net.schedule.as_ref().map(|schedule| schedule_allows(schedule)).unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Weekday};
+
+    fn window(days: Vec<cfg::Weekday>, from: &str, to: &str) -> cfg::ScheduleWindow {
+        cfg::ScheduleWindow { days, from: from.to_string(), to: to.to_string() }
+    }
+
+    fn at(time: &str) -> NaiveTime {
+        parse_time(time).unwrap()
+    }
+
+    #[test]
+    fn overnight_window_spans_midnight() {
+        let night = window(vec![], "22:00", "06:00");
+
+        assert!(window_contains(&night, Weekday::Mon, at("23:30")));
+        assert!(window_contains(&night, Weekday::Tue, at("05:59")));
+        assert!(!window_contains(&night, Weekday::Tue, at("06:00")));
+        assert!(!window_contains(&night, Weekday::Mon, at("21:59")));
+        assert!(!window_contains(&night, Weekday::Mon, at("12:00")));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_the_day_it_opens() {
+        let friday_night = window(vec![cfg::Weekday::Fri], "22:00", "06:00");
+
+        assert!(window_contains(&friday_night, Weekday::Fri, at("22:00")));
+        assert!(window_contains(&friday_night, Weekday::Sat, at("01:00")));
+        assert!(!window_contains(&friday_night, Weekday::Fri, at("01:00")));
+        assert!(!window_contains(&friday_night, Weekday::Sat, at("23:00")));
+    }
+
+    #[test]
+    fn day_window_ends_before_to() {
+        let office = window(vec![cfg::Weekday::Mon, cfg::Weekday::Fri], "09:00", "18:00");
+
+        assert!(window_contains(&office, Weekday::Mon, at("09:00")));
+        assert!(!window_contains(&office, Weekday::Mon, at("18:00")));
+        assert!(!window_contains(&office, Weekday::Tue, at("12:00")));
+    }
+
+    #[test]
+    fn timezone_shifts_time_and_weekday() {
+        let schedule = cfg::Schedule {
+            timezone: Some("Asia/Tokyo".to_string()),
+            windows: vec![window(vec![cfg::Weekday::Sat], "08:00", "10:00")],
+        };
+
+        // Friday 23:30 UTC is Saturday 08:30 in Tokyo
+        assert!(schedule_allows_at(&schedule, Utc.with_ymd_and_hms(2024, 3, 1, 23, 30, 0).unwrap()));
+        // Saturday 08:30 UTC is Saturday 17:30 in Tokyo
+        assert!(!schedule_allows_at(&schedule, Utc.with_ymd_and_hms(2024, 3, 2, 8, 30, 0).unwrap()));
+    }
+}
@@ -1,13 +1,47 @@
-use num_derive::{FromPrimitive, ToPrimitive};
-
-
-#[derive(Debug, Clone, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone)]
 pub enum NativeError {
-    AccessDenied = 5,
-    NotEnoughMemory = 8,
-    InvalidParameter = 87,
-    NotFound = 1168,
-    RemoteSessionLimitExceeded = 1220,
+    AccessDenied,
+    NotEnoughMemory,
+    InvalidParameter,
+    AlreadyExists,
+    NotFound,
+    NoMatch,
+    BadProfile,
+    RemoteSessionLimitExceeded,
+    InvalidState,
+    /// ## A code not listed above, kept for diagnostics
+    Unknown(u32),
+}
+impl NativeError {
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            5 => Self::AccessDenied,
+            8 => Self::NotEnoughMemory,
+            87 => Self::InvalidParameter,
+            183 => Self::AlreadyExists,
+            1168 => Self::NotFound,
+            1169 => Self::NoMatch,
+            1206 => Self::BadProfile,
+            1220 => Self::RemoteSessionLimitExceeded,
+            5023 => Self::InvalidState,
+            code => Self::Unknown(code),
+        }
+    }
+
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::AccessDenied => 5,
+            Self::NotEnoughMemory => 8,
+            Self::InvalidParameter => 87,
+            Self::AlreadyExists => 183,
+            Self::NotFound => 1168,
+            Self::NoMatch => 1169,
+            Self::BadProfile => 1206,
+            Self::RemoteSessionLimitExceeded => 1220,
+            Self::InvalidState => 5023,
+            Self::Unknown(code) => *code,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -16,3 +50,22 @@ pub enum Error {
 }
 
 pub type NativeResult<T> = std::result::Result<T, NativeError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_codes_are_kept() {
+        assert!(matches!(NativeError::from_code(1206), NativeError::BadProfile));
+        assert!(matches!(NativeError::from_code(1169), NativeError::NoMatch));
+        assert!(matches!(NativeError::from_code(13), NativeError::Unknown(13)));
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for code in [5, 8, 87, 183, 1168, 1169, 1206, 1220, 5023, 13] {
+            assert_eq!(NativeError::from_code(code).code(), code);
+        }
+    }
+}
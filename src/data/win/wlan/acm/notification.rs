@@ -43,7 +43,9 @@ pub enum Code {
 #[derive(Debug, Clone)]
 pub struct Notification {
     pub code: Code,
-    pub guid: GUID
+    pub guid: GUID,
+    /// ## `WLAN_REASON_CODE` of connection notifications
+    pub reason: Option<u32>,
 }
 impl Notification {
     pub fn from_l2_notification_data(data: WiFi::L2_NOTIFICATION_DATA) -> app::Result<Self> {
@@ -51,9 +53,21 @@ impl Notification {
             return Err(app::Error::NotAnAcmNotification)
         }
 
+        let code = Code::from_u32(data.NotificationCode).unwrap();
+        let reason = match code {
+            Code::ConnectionComplete | Code::ConnectionAttemptFail | Code::Disconnected
+            if !data.pData.is_null()
+            && data.dwDataSize as usize >= std::mem::size_of::<WiFi::WLAN_CONNECTION_NOTIFICATION_DATA>() => {
+                let connection = unsafe { &*(data.pData as *const WiFi::WLAN_CONNECTION_NOTIFICATION_DATA) };
+                Some(connection.wlanReasonCode)
+            },
+            _ => None
+        };
+
         let this = Self {
-            code: Code::from_u32(data.NotificationCode).unwrap(),
-            guid: data.InterfaceGuid
+            code,
+            guid: data.InterfaceGuid,
+            reason
         };
 
         Ok(this)
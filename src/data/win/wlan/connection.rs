@@ -0,0 +1,48 @@
+use super::interface::State;
+use super::network::{ssid, phy, Bssid};
+
+use widestring::U16CStr;
+use windows::Win32::NetworkManagement::WiFi;
+
+
+/// ## Attributes of the current connection on an interface
+#[derive(Debug, Clone)]
+pub struct Attributes {
+    pub state: State,
+    pub profile: String,
+    pub ssid: String,
+    pub bssid: Bssid,
+    pub phy: phy::Type,
+    pub signal_quality: u32,
+    /// ## Receiving rate, in kbps
+    pub rx_rate: u32,
+    /// ## Transmission rate, in kbps
+    pub tx_rate: u32,
+}
+impl Attributes {
+    pub fn from_wlan_connection_attributes(attrs: &WiFi::WLAN_CONNECTION_ATTRIBUTES) -> Self {
+        let assoc = &attrs.wlanAssociationAttributes;
+        let profile = U16CStr::from_slice_truncate(attrs.strProfileName.as_slice())
+            .map(|u16cs| u16cs.to_string_lossy())
+            .unwrap_or_default();
+
+        Self {
+            state: State::from_wlan_interface_state(attrs.isState),
+            profile,
+            ssid: ssid::from_dot11_ssid(&assoc.dot11Ssid),
+            bssid: Bssid::from_dot11_bssid(assoc.dot11Bssid),
+            phy: phy::Type::from_dot11_phy_type(assoc.dot11PhyType),
+            signal_quality: assoc.wlanSignalQuality,
+            rx_rate: assoc.ulRxRate,
+            tx_rate: assoc.ulTxRate,
+        }
+    }
+}
+
+/// ## Whether a `WLAN_REASON_CODE` means the security handshake failed
+/// 
+/// Usually that's a wrong key or EAP credentials.
+pub fn is_security_failure(reason: u32) -> bool {
+    reason == WiFi::WLAN_REASON_CODE_SECURITY_FAILURE
+    || (WiFi::WLAN_REASON_CODE_MSMSEC_BASE..=WiFi::WLAN_REASON_CODE_MSMSEC_END).contains(&reason)
+}
@@ -2,8 +2,9 @@ pub mod acm;
 pub mod interface;
 pub mod network;
 pub mod notification;
+pub mod connection;
 pub use interface::Interface;
-pub use network::Network;
+pub use network::{Network, AccessPoint, Bssid};
 
 use crate::data::win;
 use crate::data::win::SafePCWSTR;
@@ -11,9 +12,10 @@ use crate::data::win::wlan::acm::notification::Code as AcmNotifCode;
 use crate::data::win::wlan::acm::notification::Notification as AcmNotif;
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
 use tokio::sync::broadcast;
-use log::debug;
+use log::{debug, info};
 use once_cell::sync::Lazy;
 use num_traits::{FromPrimitive, ToPrimitive};
 use num_derive::{FromPrimitive, ToPrimitive};
@@ -33,6 +35,8 @@ static mut ACM_NOTIFY_SENDERS: Lazy<HashMap<u32, broadcast::Sender<AcmNotif>>> =
 pub struct Session {
     id: u32,
     acm_notify_receiver: broadcast::Receiver<AcmNotif>,
+    /// ## Reason code of the last failed `Wlan::connect`, `0` if none
+    last_fail_reason: AtomicU32,
 }
 
 
@@ -49,6 +53,8 @@ pub struct Wlan {
     session: Session,
     handle: HANDLE,
     negotiated_version: ClientVersion,
+    /// ## Log changes instead of making them
+    dry_run: bool,
 }
 // --------- Constructors ---------
 impl Wlan {
@@ -67,7 +73,7 @@ impl Wlan {
         };
         
         if handle_result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(handle_result).unwrap())
+            return Err(win::NativeError::from_code(handle_result))
         }
 
         let (acm_notify_sender, acm_notify_receiver) = {
@@ -79,12 +85,14 @@ impl Wlan {
         let session = Session {
             id,
             acm_notify_receiver,
+            last_fail_reason: AtomicU32::new(0),
         };
 
         let this = Self {
             session,
             handle,
             negotiated_version: ClientVersion::from_u32(negotiated_version).unwrap(),
+            dry_run: false,
         };
 
         this.register_acm_notifs()?;
@@ -92,11 +100,35 @@ impl Wlan {
         Ok(this)
     }
 }
+// --------- Modes ---------
+impl Wlan {
+    /// ## Only pretend to change anything
+    /// 
+    /// Connecting, disconnecting and storing or deleting
+    /// profiles are logged and reported as successful,
+    /// everything else works as usual.
+    pub fn into_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
 // --------- Getters ---------
 impl Wlan {
     pub fn negotiated_version(&self) -> &ClientVersion {
         &self.negotiated_version
     }
+
+    /// ## Reason code of the last failed connection attempt
+    pub fn last_fail_reason(&self) -> Option<u32> {
+        match self.session.last_fail_reason.load(Ordering::Relaxed) {
+            0 => None,
+            reason => Some(reason)
+        }
+    }
 }
 // --------- Callbacks ---------
 impl Wlan {
@@ -124,7 +156,7 @@ impl Wlan {
         };
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            return Err(win::NativeError::from_code(result))
         }
 
         Ok(())
@@ -152,7 +184,7 @@ impl Wlan {
         let list_result = unsafe { WiFi::WlanEnumInterfaces(self.handle, None, &mut list) };
 
         if list_result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(list_result).unwrap())
+            return Err(win::NativeError::from_code(list_result))
         }
 
         unsafe {
@@ -195,7 +227,7 @@ impl Wlan {
         };
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            return Err(win::NativeError::from_code(result))
         }
 
         loop {
@@ -204,7 +236,7 @@ impl Wlan {
             };
 
             let timeout = tokio::time::timeout(
-                Duration::from_millis(crate::CONFIG.get().unwrap().wifi.scan.timeout_ms),
+                Duration::from_millis(crate::config().wifi.scan.timeout_ms),
                 async move { acm_notify_receiver.recv().await }
             ).await;
 
@@ -252,7 +284,7 @@ impl Wlan {
         };
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            return Err(win::NativeError::from_code(result))
         }
 
         let networks = Network::from_wlan_available_network_list(raw_networks);
@@ -264,6 +296,101 @@ impl Wlan {
         Ok(networks)
     }
 
+    /// ## Access points visible to the interface
+    /// 
+    /// Unlike `Wlan::available_networks`, which merges all
+    /// access points of the same SSID into one `Network`,
+    /// this one lists each BSS separately, with its own
+    /// BSSID, RSSI, link quality and frequency.
+    /// 
+    /// ## Parameters
+    /// - `guid`: A GUID of the interface from which
+    /// the access points will return.
+    /// - `ssid`: Only return access points of this SSID.
+    /// `None` returns all of them.
+    /// 
+    /// ## Returns
+    /// `Result` wraps 2 values:
+    /// - An error, returned by a `WlanGetNetworkBssList` function.
+    /// - A `Vec` of all currently visible access points.
+    pub fn access_points(&self, guid: &GUID, ssid: Option<&str>) -> win::NativeResult<Vec<AccessPoint>> {
+        let mut raw_list = unsafe { std::mem::zeroed() };
+        let dot11_ssid = ssid.map(|ssid| network::ssid::to_dot11_ssid(ssid));
+
+        let result = unsafe {
+            WiFi::WlanGetNetworkBssList(
+                self.handle,
+                guid,
+                dot11_ssid.as_ref().map(|ssid| ssid as *const WiFi::DOT11_SSID),
+                WiFi::dot11_BSS_type_any,
+                false,
+                None,
+                &mut raw_list
+            )
+        };
+
+        if result != win::SUCCESS {
+            return Err(win::NativeError::from_code(result))
+        }
+
+        let access_points = unsafe {
+            AccessPoint::from_wlan_bss_list(raw_list)
+        };
+
+        unsafe {
+            WiFi::WlanFreeMemory(raw_list as *const core::ffi::c_void)
+        }
+
+        Ok(access_points)
+    }
+
+    /// ## Current connection of the interface
+    /// 
+    /// ## Parameters
+    /// - `guid`: A GUID of the interface to query.
+    /// 
+    /// ## Returns
+    /// `Result` wraps 2 values:
+    /// - An error, returned by a `WlanQueryInterface` function.
+    /// - `None` if the interface is not connected,
+    /// connection attributes otherwise.
+    pub fn current_connection(&self, guid: &GUID) -> win::NativeResult<Option<connection::Attributes>> {
+        let mut size = 0;
+        let mut data = std::ptr::null_mut();
+
+        let result = unsafe {
+            WiFi::WlanQueryInterface(
+                self.handle,
+                guid,
+                WiFi::wlan_intf_opcode_current_connection,
+                None,
+                &mut size,
+                &mut data,
+                None
+            )
+        };
+
+        if result == win::NativeError::InvalidState.code() {
+            return Ok(None)
+        }
+
+        if result != win::SUCCESS {
+            return Err(win::NativeError::from_code(result))
+        }
+
+        let attrs = unsafe {
+            connection::Attributes::from_wlan_connection_attributes(
+                &*(data as *const WiFi::WLAN_CONNECTION_ATTRIBUTES)
+            )
+        };
+
+        unsafe {
+            WiFi::WlanFreeMemory(data as *const core::ffi::c_void)
+        }
+
+        Ok(Some(attrs))
+    }
+
     /// ## Get saved WI-FI network
     /// 
     /// When you connect to some WI-FI, Windows saves
@@ -283,13 +410,17 @@ impl Wlan {
     /// the profile will return.
     /// - `name`: The name of a profile.
     /// Matches the network SSID.
+    /// - `plaintext_key`: Decrypt the key. Only works
+    /// with admin rights, otherwise it stays encrypted.
     /// 
     /// ## Returns
     /// `Result` wraps 2 values:
-    /// - An error, returned by a `WlanGetProfile` function.
+    /// - An error, returned by a `WlanGetProfile` function,
+    /// or `BadProfile` if the XML can't be parsed.
     /// - A deserialized profile.
-    pub fn get_profile(&self, guid: &GUID, name: &str) -> win::NativeResult<network::profile::Profile> {
+    pub fn get_profile(&self, guid: &GUID, name: &str, plaintext_key: bool) -> win::NativeResult<network::profile::Profile> {
         let mut profile_pwstr = unsafe { std::mem::zeroed() };
+        let mut flags = if plaintext_key { WiFi::WLAN_PROFILE_GET_PLAINTEXT_KEY } else { 0 };
 
         let name_u16cs = widestring::U16CString::from_str(name).unwrap();
         let name_pcwstr = windows::core::PCWSTR::from_raw(name_u16cs.as_ptr());
@@ -301,36 +432,39 @@ impl Wlan {
                 name_pcwstr,
                 None,
                 &mut profile_pwstr,
-                None,
+                Some(&mut flags),
                 None
             )
         };
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            return Err(win::NativeError::from_code(result))
         }
 
         let string_profile = win::util::string::from_pwstr(&profile_pwstr).unwrap();
-        let profile = network::profile::Profile::deserialize_str(&string_profile).unwrap();
 
         unsafe {
             WiFi::WlanFreeMemory(profile_pwstr.as_ptr() as *const core::ffi::c_void)
         };
 
-        Ok(profile)
+        network::profile::Profile::deserialize_str(&string_profile).map_err(|err| {
+            debug!("wlan: can't parse profile {} ({})", name, err);
+            win::NativeError::BadProfile
+        })
     }
 
     pub fn profile_exists(&self, guid: &GUID, name: &str) -> bool {
-        if let Err(win::NativeError::NotFound) = self.get_profile(guid, name) {
+        if let Err(win::NativeError::NotFound) = self.get_profile(guid, name, false) {
             return false
         }
 
         true
     }
 
-    pub fn list_profiles(&self, guid: &GUID) -> win::NativeResult<Vec<network::Profile>> {
+    /// ## Names of the stored profiles, in the order of preference
+    pub fn list_profile_names(&self, guid: &GUID) -> win::NativeResult<Vec<String>> {
         let mut list = unsafe { std::mem::zeroed() };
-        let mut parsed_list = vec![];
+        let mut names = vec![];
 
         let result = unsafe {
             WiFi::WlanGetProfileList(
@@ -342,7 +476,7 @@ impl Wlan {
         };
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            return Err(win::NativeError::from_code(result))
         }
 
         unsafe {
@@ -351,25 +485,37 @@ impl Wlan {
                 let u16cs = U16CStr::from_slice_truncate(
                     (*profile_info).strProfileName.as_slice()
                 ).unwrap();
-                let profile_name = u16cs.to_string().unwrap();
-
-                let profile_result = self.get_profile(guid, &profile_name);
-                if profile_result.is_err() {
-                    continue;
-                }
-                let profile = profile_result.unwrap();
-
-                parsed_list.push(profile)
+                names.push(u16cs.to_string().unwrap())
             }
+
+            WiFi::WlanFreeMemory(list as *const core::ffi::c_void)
         }
 
-        Ok(parsed_list)
+        Ok(names)
+    }
+
+    /// ## Stored profiles that could be read
+    /// 
+    /// Profiles that can't be read are left out,
+    /// see `Wlan::list_profile_names` to tell them apart.
+    pub fn list_profiles(&self, guid: &GUID, plaintext_keys: bool) -> win::NativeResult<Vec<network::Profile>> {
+        let names = self.list_profile_names(guid)?;
+
+        Ok(names.iter()
+            .filter_map(|name| self.get_profile(guid, name, plaintext_keys).ok())
+            .collect())
     }
 
-    pub fn set_profile(&self, guid: &GUID, profile: network::Profile) -> win::NativeResult<()> {
+    pub fn set_profile(&self, guid: &GUID, profile: network::Profile, overwrite: bool) -> win::NativeResult<()> {
+        if self.dry_run {
+            info!("o DRY RUN: would store profile {} on {} (overwrite: {})", profile.name, win::guid::to_string(guid), overwrite);
+            return Ok(())
+        }
+
         let mut reason_code = 0;
         let profile_string = profile.genuine_serialize_to_string();
-        let profile_u16cs = widestring::U16CString::from_str(&profile_string).unwrap();
+        let profile_u16cs = widestring::U16CString::from_str(&profile_string)
+            .map_err(|_| win::NativeError::InvalidParameter)?;
         let profile_pcwstr = windows::core::PCWSTR::from_raw(profile_u16cs.as_ptr());
 
         let result = unsafe {
@@ -379,18 +525,118 @@ impl Wlan {
                 0,
                 profile_pcwstr,
                 None,
-                false,
+                overwrite,
                 None,
                 &mut reason_code
             )
         };
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            debug!("wlan: WlanSetProfile failed with {} (reason code {})", result, reason_code);
+            return Err(win::NativeError::from_code(result))
+        }
+
+        if reason_code != WiFi::WLAN_REASON_CODE_SUCCESS {
+            debug!("wlan: WlanSetProfile rejected the profile (reason code {})", reason_code);
+            return Err(win::NativeError::BadProfile)
+        }
+
+        Ok(())
+    }
+
+    pub fn delete_profile(&self, guid: &GUID, name: &str) -> win::NativeResult<()> {
+        if self.dry_run {
+            info!("o DRY RUN: would delete profile {} on {}", name, win::guid::to_string(guid));
+            return Ok(())
+        }
+
+        let name_u16cs = widestring::U16CString::from_str(name).unwrap();
+        let name_pcwstr = windows::core::PCWSTR::from_raw(name_u16cs.as_ptr());
+
+        let result = unsafe {
+            WiFi::WlanDeleteProfile(
+                self.handle,
+                guid,
+                name_pcwstr,
+                None
+            )
+        };
+
+        if result != win::SUCCESS {
+            return Err(win::NativeError::from_code(result))
+        }
+
+        Ok(())
+    }
+
+    /// ## Move a profile in the preference list
+    /// 
+    /// Position `0` is the most preferred one.
+    /// Group policy profiles always stay on top.
+    pub fn set_profile_position(&self, guid: &GUID, name: &str, position: u32) -> win::NativeResult<()> {
+        if self.dry_run {
+            info!("o DRY RUN: would move profile {} on {} to {}", name, win::guid::to_string(guid), position);
+            return Ok(())
+        }
+
+        let name_u16cs = widestring::U16CString::from_str(name).unwrap();
+        let name_pcwstr = windows::core::PCWSTR::from_raw(name_u16cs.as_ptr());
+
+        let result = unsafe {
+            WiFi::WlanSetProfilePosition(
+                self.handle,
+                guid,
+                name_pcwstr,
+                position,
+                None
+            )
+        };
+
+        if result != win::SUCCESS {
+            return Err(win::NativeError::from_code(result))
         }
 
-        if reason_code != 0 {
-            panic!("how to handle reason codes idk")
+        Ok(())
+    }
+
+    /// ## Store EAP user credentials for a profile
+    /// 
+    /// WPA-Enterprise profiles don't hold the username
+    /// and password themselves, they are stored separately
+    /// for the profile and used on every connection.
+    /// 
+    /// ## Parameters
+    /// - `guid`: A GUID of the interface the profile is on.
+    /// - `name`: The name of a profile.
+    /// - `user_data`: `EapHostUserCredentials` XML, see
+    /// `network::profile::onex::user_credentials_xml`.
+    /// 
+    /// ## Returns
+    /// An error, returned by a `WlanSetProfileEapXmlUserData` function.
+    pub fn set_profile_eap_user_data(&self, guid: &GUID, name: &str, user_data: &str) -> win::NativeResult<()> {
+        if self.dry_run {
+            info!("o DRY RUN: would store EAP credentials of {} on {}", name, win::guid::to_string(guid));
+            return Ok(())
+        }
+
+        let name_u16cs = widestring::U16CString::from_str(name).unwrap();
+        let name_pcwstr = windows::core::PCWSTR::from_raw(name_u16cs.as_ptr());
+        let data_u16cs = widestring::U16CString::from_str(user_data).unwrap();
+        let data_pcwstr = windows::core::PCWSTR::from_raw(data_u16cs.as_ptr());
+
+        let result = unsafe {
+            WiFi::WlanSetProfileEapXmlUserData(
+                self.handle,
+                guid,
+                name_pcwstr,
+                WiFi::WLAN_SET_EAPHOST_FLAGS(0),
+                data_pcwstr,
+                None
+            )
+        };
+
+        if result != win::SUCCESS {
+            return Err(win::NativeError::from_code(result))
         }
 
         Ok(())
@@ -398,11 +644,15 @@ impl Wlan {
 
     fn wlan_connection_params_safe(
         profile_pcwstr: SafePCWSTR,
-        bss: &network::Bss
+        bss: &network::Bss,
+        bssid_list: Option<&mut WiFi::DOT11_BSSID_LIST>
     ) -> network::SafeConnectionParameters {
         let params = WiFi::WLAN_CONNECTION_PARAMETERS {
             wlanConnectionMode: WiFi::wlan_connection_mode_profile,
             strProfile: profile_pcwstr.0,
+            pDesiredBssidList: bssid_list
+                .map(|list| list as *mut WiFi::DOT11_BSSID_LIST)
+                .unwrap_or(std::ptr::null_mut()),
             dot11BssType: bss.to_dot11_bss_type(),
             ..Default::default()
         };
@@ -425,26 +675,53 @@ impl Wlan {
         }
     }
 
+    /// ## Connect to a WI-FI network
+    /// 
+    /// ## Parameters
+    /// - `guid`: A GUID of the interface to connect on.
+    /// - `profile`: The name of a profile to connect with.
+    /// - `bss`: The BSS type of the network.
+    /// - `bssid`: The access point to associate with.
+    /// `None` lets Windows choose one on its own.
+    /// 
+    /// ## Returns
+    /// `Result` wraps 2 values:
+    /// - An error, returned by a `WlanConnect` function.
+    /// - A `bool` value. `true` means that connection was successful,
+    /// and `false` means that it failed or timed out.
     pub async fn connect(
         &self,
         guid: &GUID,
         profile: &str,
         bss: &network::Bss,
+        bssid: Option<&Bssid>,
     ) -> win::NativeResult<bool> {
+        if self.dry_run {
+            info!(
+                "o DRY RUN: would connect {} to {}{}",
+                win::guid::to_string(guid),
+                profile,
+                bssid.map(|bssid| format!(" via {}", bssid)).unwrap_or_default()
+            );
+            return Ok(true)
+        }
+
         let profile_u16cs = widestring::U16CString::from_str(profile).unwrap();
         let profile_pcwstr = super::util::from_u16cstring_safe(&profile_u16cs);
+        let mut bssid_list = bssid.map(|bssid| bssid.to_dot11_bssid_list());
 
-        let params = Self::wlan_connection_params_safe(profile_pcwstr, bss);
+        let params = Self::wlan_connection_params_safe(profile_pcwstr, bss, bssid_list.as_mut());
         
+        self.session.last_fail_reason.store(0, Ordering::Relaxed);
         let result = self.connect_safe(guid, params);
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            return Err(win::NativeError::from_code(result))
         }
 
         loop {
             let timeout = tokio::time::timeout(
-                Duration::from_millis(crate::CONFIG.get().unwrap().wifi.connect.timeout_ms),
+                Duration::from_millis(crate::config().wifi.connect.timeout_ms),
                 async move { self.session.acm_notify_receiver.resubscribe().recv().await }
             ).await;
             if timeout.is_err() { break }
@@ -453,7 +730,10 @@ impl Wlan {
             match notif.code {
                 AcmNotifCode::ConnectionStart => (),
                 AcmNotifCode::ConnectionComplete => return Ok(true),
-                AcmNotifCode::ConnectionAttemptFail => return Ok(false),
+                AcmNotifCode::ConnectionAttemptFail => {
+                    self.session.last_fail_reason.store(notif.reason.unwrap_or(0), Ordering::Relaxed);
+                    return Ok(false)
+                },
                 _ => debug!("Wlan::connect() recv {:?}", notif.code)
             }
         }
@@ -462,17 +742,22 @@ impl Wlan {
     }
 
     pub async fn disconnect(&self, guid: &GUID) -> win::NativeResult<bool> {
+        if self.dry_run {
+            info!("o DRY RUN: would disconnect {}", win::guid::to_string(guid));
+            return Ok(true)
+        }
+
         let result = unsafe {
             WiFi::WlanDisconnect(self.handle, guid, None)
         };
 
         if result != win::SUCCESS {
-            return Err(win::NativeError::from_u32(result).unwrap())
+            return Err(win::NativeError::from_code(result))
         }
 
         loop {
             let timeout = tokio::time::timeout(
-                Duration::from_millis(crate::CONFIG.get().unwrap().wifi.disconnect.timeout_ms),
+                Duration::from_millis(crate::config().wifi.disconnect.timeout_ms),
                 async move { self.session.acm_notify_receiver.resubscribe().recv().await }
             ).await;
             if timeout.is_err() { break }
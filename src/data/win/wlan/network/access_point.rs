@@ -0,0 +1,84 @@
+use super::{ssid, phy, Bss, Bssid, Band};
+
+use windows::Win32::NetworkManagement::WiFi;
+
+
+/// ## A single BSS of some network
+/// 
+/// One SSID can be served by several access points
+/// (i.e. two modems sharing the same name), each one
+/// has its own `Bssid`, signal and frequency.
+#[derive(Debug, Clone)]
+pub struct AccessPoint {
+    pub ssid: String,
+    pub bssid: Bssid,
+    pub bss: Bss,
+    pub phy: phy::Type,
+    /// ## Received signal strength, in dBm
+    pub rssi: i32,
+    /// ## Link quality, from 0 to 100
+    pub link_quality: u32,
+    /// ## Channel center frequency, in kHz
+    pub frequency_khz: u32,
+}
+// --------- Constructors ---------
+impl AccessPoint {
+    pub fn from_wlan_bss_entry(entry: &WiFi::WLAN_BSS_ENTRY) -> Self {
+        Self {
+            ssid: ssid::from_dot11_ssid(&entry.dot11Ssid),
+            bssid: Bssid::from_dot11_bssid(entry.dot11Bssid),
+            bss: Bss::from_dot11_bss_type(entry.dot11BssType),
+            phy: phy::Type::from_dot11_phy_type(entry.dot11BssPhyType),
+            rssi: entry.lRssi,
+            link_quality: entry.uLinkQuality,
+            frequency_khz: entry.ulChCenterFrequency,
+        }
+    }
+
+    /// ## Read every entry of a BSS list
+    /// 
+    /// # Safety
+    /// `list` must point to a valid `WLAN_BSS_LIST`,
+    /// i.e. one returned by `WlanGetNetworkBssList`
+    /// and not freed yet.
+    pub unsafe fn from_wlan_bss_list(list: *mut WiFi::WLAN_BSS_LIST) -> Vec<Self> {
+        let mut output = vec![];
+
+        for idx in 0..(*list).dwNumberOfItems {
+            let entry = (*list).wlanBssEntries.as_ptr().add(idx as usize);
+            output.push(Self::from_wlan_bss_entry(&*entry));
+        }
+
+        output
+    }
+}
+// --------- Getters ---------
+impl AccessPoint {
+    pub fn band(&self) -> Option<Band> {
+        Band::from_frequency_khz(self.frequency_khz)
+    }
+
+    pub fn generation(&self) -> Option<phy::Generation> {
+        self.phy.generation()
+    }
+
+    pub fn frequency_mhz(&self) -> u32 {
+        self.frequency_khz / 1000
+    }
+
+    /// ## Channel number derived from the center frequency
+    /// 
+    /// Returns `None` if the frequency doesn't belong
+    /// to any of 2.4, 5 or 6 GHz bands.
+    pub fn channel(&self) -> Option<u32> {
+        let mhz = self.frequency_mhz();
+
+        match mhz {
+            2484 => Some(14),
+            2412..=2472 => Some((mhz - 2407) / 5),
+            5955..=7115 => Some((mhz - 5950) / 5),
+            5160..=5885 => Some((mhz - 5000) / 5),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use serde_derive::{Serialize, Deserialize};
+use schemars::JsonSchema;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Band {
+    #[serde(rename = "2.4GHz")]
+    TwoPointFourGhz,
+    #[serde(rename = "5GHz")]
+    FiveGhz,
+    #[serde(rename = "6GHz")]
+    SixGhz,
+}
+impl Band {
+    pub fn from_frequency_khz(khz: u32) -> Option<Self> {
+        match khz / 1000 {
+            2400..=2500 => Some(Self::TwoPointFourGhz),
+            5925..=7125 => Some(Self::SixGhz),
+            5150..=5924 => Some(Self::FiveGhz),
+            _ => None
+        }
+    }
+}
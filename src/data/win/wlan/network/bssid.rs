@@ -0,0 +1,70 @@
+use std::fmt;
+use std::str::FromStr;
+use windows::Win32::NetworkManagement::{WiFi, Ndis};
+
+
+#[derive(Debug, Clone)]
+pub enum ParseError {
+    InvalidLength,
+    InvalidOctet(String)
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "it needs 6 octets"),
+            Self::InvalidOctet(octet) => write!(f, "{:?} is not a hex octet", octet),
+        }
+    }
+}
+
+/// ## MAC address of a single access point
+/// 
+/// Displayed and parsed as `AA:BB:CC:DD:EE:FF`,
+/// `-` is accepted as a separator too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bssid(pub [u8; 6]);
+impl Bssid {
+    pub fn from_dot11_bssid(bssid: [u8; 6]) -> Self {
+        Self(bssid)
+    }
+
+    pub fn to_dot11_bssid_list(&self) -> WiFi::DOT11_BSSID_LIST {
+        WiFi::DOT11_BSSID_LIST {
+            Header: Ndis::NDIS_OBJECT_HEADER {
+                Type: Ndis::NDIS_OBJECT_TYPE_DEFAULT as u8,
+                Revision: WiFi::DOT11_BSSID_LIST_REVISION_1 as u8,
+                Size: std::mem::size_of::<WiFi::DOT11_BSSID_LIST>() as u16,
+            },
+            uNumOfEntries: 1,
+            uTotalNumOfEntries: 1,
+            BSSIDs: self.0,
+        }
+    }
+}
+impl fmt::Display for Bssid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let octets = self.0.iter()
+            .map(|octet| format!("{:02X}", octet))
+            .collect::<Vec<String>>();
+
+        write!(f, "{}", octets.join(":"))
+    }
+}
+impl FromStr for Bssid {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let octets = s.split(|c| c == ':' || c == '-').collect::<Vec<&str>>();
+        if octets.len() != 6 {
+            return Err(ParseError::InvalidLength)
+        }
+
+        let mut bssid = [0u8; 6];
+        for (idx, octet) in octets.iter().enumerate() {
+            bssid[idx] = u8::from_str_radix(octet, 16)
+                .map_err(|_| ParseError::InvalidOctet(octet.to_string()))?;
+        }
+
+        Ok(Self(bssid))
+    }
+}
@@ -2,8 +2,15 @@ pub mod profile;
 pub mod security;
 pub mod phy;
 pub mod bss;
+pub mod bssid;
+pub mod ssid;
+pub mod access_point;
+pub mod band;
 pub use security::{auth::{self, Authentication}, cipher::{self, Encryption}};
 pub use bss::Bss;
+pub use bssid::Bssid;
+pub use access_point::AccessPoint;
+pub use band::Band;
 pub use profile::Profile;
 
 use security::Security;
@@ -37,13 +44,7 @@ impl Network {
         let profile = String::from_utf16(network.strProfileName.as_slice())
             .ok()
             .filter(|string| string.is_empty());
-        let ssid = unsafe {
-            let slice = std::ptr::slice_from_raw_parts(
-                network.dot11Ssid.ucSSID.as_ptr(),
-                network.dot11Ssid.uSSIDLength as usize
-            ).as_ref().unwrap();
-            String::from_utf8_lossy(slice).to_string()
-        };
+        let ssid = ssid::from_dot11_ssid(&network.dot11Ssid);
         let bss = Bss::from_dot11_bss_type(network.dot11BssType);
         let bssids = network.uNumberOfBssids;
         let connectable = network.bNetworkConnectable.as_bool();
@@ -98,9 +99,27 @@ impl Network {
         output
     }
 }
+// --------- Getters ---------
+impl Network {
+    /// ## The newest 802.11 generation this network supports
+    pub fn best_generation(&self) -> Option<phy::Generation> {
+        self.phys.iter().filter_map(|phy| phy.generation()).max()
+    }
+}
 // --------- Converters ---------
 impl Network {
-    pub fn to_profile(self, key: Option<profile::Key>) -> Profile {
+    pub fn to_profile(self, key: Option<profile::Key>) -> Result<Profile, profile::UnsupportedSecurity> {
         Profile::from_network(self, key)
     }
+
+    /// ## Check that a profile can be made for this network
+    pub fn check_supported(&self) -> Result<(), profile::UnsupportedSecurity> {
+        profile::raw::ConnectionType::from_bss(self.bss.clone())?;
+        profile::raw::security_from_network(
+            self.security.auth.clone(),
+            self.security.cipher.clone()
+        )?;
+
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -1,27 +1,90 @@
-use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
+use serde_derive::{Serialize, Deserialize};
+use schemars::JsonSchema;
 use windows::Win32::NetworkManagement::WiFi;
 
 
-#[derive(Debug, Clone, FromPrimitive)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Type {
-    Any = 0,
-    Fhss = 1,
-    Dsss = 2,
-    IrBaseBand = 3,
-    Ofdm = 4,
-    Hrdsss = 5,
-    Erp = 6,
-    Ht = 7,
-    Vht = 8,
-    Dmg = 9,
-    He = 10,
-    Eht = 11,
-    IhvStart = -2147483648,
-    IhvEnd = -1
+    Any,
+    Fhss,
+    Dsss,
+    IrBaseBand,
+    Ofdm,
+    Hrdsss,
+    Erp,
+    Ht,
+    Vht,
+    Dmg,
+    He,
+    Eht,
+    /// ## Vendor-specific, from `0x80000000` up
+    Ihv(u32),
+    /// ## Newer than this list
+    Unknown(i32),
 }
 impl Type {
     pub fn from_dot11_phy_type(phy: WiFi::DOT11_PHY_TYPE) -> Self {
-        Self::from_i32(phy.0).unwrap()
+        match phy.0 {
+            0 => Self::Any,
+            1 => Self::Fhss,
+            2 => Self::Dsss,
+            3 => Self::IrBaseBand,
+            4 => Self::Ofdm,
+            5 => Self::Hrdsss,
+            6 => Self::Erp,
+            7 => Self::Ht,
+            8 => Self::Vht,
+            9 => Self::Dmg,
+            10 => Self::He,
+            11 => Self::Eht,
+            ihv if ihv < 0 => Self::Ihv(ihv as u32),
+            unknown => Self::Unknown(unknown),
+        }
+    }
+}
+
+/// ## 802.11 standard generation
+/// 
+/// Ordered from the oldest to the newest,
+/// so generations can be compared with `<` and `>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Generation {
+    /// ## 802.11a/b/g and older
+    Legacy,
+    /// ## 802.11n (WI-FI 4)
+    N,
+    /// ## 802.11ac (WI-FI 5)
+    Ac,
+    /// ## 802.11ax (WI-FI 6)
+    Ax,
+    /// ## 802.11be (WI-FI 7)
+    Be,
+}
+impl Type {
+    pub fn generation(&self) -> Option<Generation> {
+        match self {
+            Self::Fhss | Self::Dsss | Self::IrBaseBand
+            | Self::Ofdm | Self::Hrdsss | Self::Erp => Some(Generation::Legacy),
+            Self::Ht => Some(Generation::N),
+            Self::Vht => Some(Generation::Ac),
+            Self::He => Some(Generation::Ax),
+            Self::Eht => Some(Generation::Be),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vendor_and_unknown_types_dont_panic() {
+        assert_eq!(Type::from_dot11_phy_type(WiFi::DOT11_PHY_TYPE(10)), Type::He);
+        assert_eq!(Type::from_dot11_phy_type(WiFi::DOT11_PHY_TYPE(i32::MIN)), Type::Ihv(0x8000_0000));
+        assert_eq!(Type::from_dot11_phy_type(WiFi::DOT11_PHY_TYPE(-1)), Type::Ihv(0xFFFF_FFFF));
+        assert_eq!(Type::from_dot11_phy_type(WiFi::DOT11_PHY_TYPE(42)), Type::Unknown(42));
+        assert_eq!(Type::Ihv(0x8000_0000).generation(), None);
     }
 }
@@ -0,0 +1,25 @@
+use crate::data::win::wlan::network::{self, Bss};
+
+
+/// ## Security the profile schema has no place for
+/// 
+/// Returned when a scanned network can't be turned
+/// into a profile, so it's skipped instead of
+/// crashing in the middle of a switch.
+#[derive(Debug, Clone)]
+pub enum UnsupportedSecurity {
+    Authentication(network::Authentication),
+    Encryption(network::Encryption),
+    Combination(network::Authentication, network::Encryption),
+    Bss(Bss),
+}
+impl std::fmt::Display for UnsupportedSecurity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Authentication(auth) => write!(f, "unsupported authentication {:?}", auth),
+            Self::Encryption(cipher) => write!(f, "unsupported encryption {:?}", cipher),
+            Self::Combination(auth, cipher) => write!(f, "unsupported combination of {:?} and {:?}", auth, cipher),
+            Self::Bss(bss) => write!(f, "unsupported BSS type {:?}", bss),
+        }
+    }
+}
@@ -1,7 +1,18 @@
 pub mod raw;
+pub mod onex;
+pub mod error;
+pub mod patch;
+pub mod protector;
+pub use error::UnsupportedSecurity;
+pub use patch::Patch;
+pub use protector::{KeyProtector, ProtectError};
 
 use crate::data::win::wlan::Network;
 
+use serde_derive::{Serialize, Deserialize};
+use schemars::JsonSchema;
+use log::warn;
+
 
 #[derive(Debug, Clone)]
 pub struct Mac {
@@ -19,7 +30,7 @@ pub struct Connection {
     pub mode: raw::ConnectionMode,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Key {
     pub kind: raw::KeyType,
     pub is_encrypted: bool,
@@ -33,47 +44,185 @@ impl Key {
             content: password.to_string()
         }
     }
+
+    /// ## Same key, but protected
+    pub fn protect(&self, protector: &dyn KeyProtector) -> Result<Self, ProtectError> {
+        if self.is_encrypted {
+            return Ok(self.clone())
+        }
+
+        Ok(Self {
+            kind: self.kind.clone(),
+            is_encrypted: true,
+            content: protector.protect(&self.content)?
+        })
+    }
+
+    /// ## Plaintext of the key
+    pub fn reveal(&self, protector: &dyn KeyProtector) -> Result<String, ProtectError> {
+        if self.is_encrypted {
+            protector.unprotect(&self.content)
+        } else {
+            Ok(self.content.clone())
+        }
+    }
+
+    /// ## Whether both keys have the same plaintext
+    pub fn matches(&self, other: &Key, protector: &dyn KeyProtector) -> Result<bool, ProtectError> {
+        Ok(self.reveal(protector)? == other.reveal(protector)?)
+    }
+}
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Key")
+            .field("kind", &self.kind)
+            .field("is_encrypted", &self.is_encrypted)
+            .field("content", &Profile::REDACTED)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum EapMethod {
+    /// ## PEAP with MSCHAPv2 inside
+    PeapMsChapV2,
+    /// ## EAP-TTLS with MSCHAPv2 inside
+    Ttls,
+    /// ## EAP-TLS with a client certificate
+    Tls,
+}
+
+/// ## 802.1X settings of a WPA-Enterprise network
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enterprise {
+    pub method: EapMethod,
+    /// ## RADIUS server names to accept, separated by `;`
+    pub server_names: Option<String>,
+    /// ## Thumbprints of trusted root CAs
+    pub ca_thumbprints: Vec<String>,
+    /// ## Thumbprint of the issuer of the client certificate (EAP-TLS)
+    pub certificate_thumbprint: Option<String>,
+    pub anonymous_identity: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Security {
     pub auth: raw::Authentication,
     pub cipher: raw::Encryption,
-    pub key: Option<Key>
+    pub key: Option<Key>,
+    pub enterprise: Option<Enterprise>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Profile {
     pub name: String,
     pub ssid: String,
+    pub non_broadcast: bool,
     pub connection: Connection,
     pub auto_switch: Option<bool>,
     pub security: Security,
-    pub mac: Mac
+    pub mac: Mac,
+    /// ## The XML this profile was parsed from
+    /// 
+    /// Kept to serialize the profile back without
+    /// losing elements `raw::WLANProfile` doesn't know.
+    pub source: Option<String>,
 }
 impl Profile {
     pub fn deserialize_str(string: &str) -> Result<Self, quick_xml::DeError> {
-        raw::WLANProfile::deserialize_str(string).map(|raw| raw.to_friendly())
+        let mut this = raw::WLANProfile::deserialize_str(string).map(|raw| raw.to_friendly())?;
+        this.source = Some(string.to_string());
+
+        Ok(this)
     }
 
-    pub fn from_network(network: Network, key: Option<Key>) -> Self {
-        Self {
+    pub fn from_network(network: Network, key: Option<Key>) -> Result<Self, UnsupportedSecurity> {
+        let kind = raw::ConnectionType::from_bss(network.bss)?;
+        let (auth, cipher) = raw::security_from_network(
+            network.security.auth,
+            network.security.cipher
+        )?;
+        // WEP keys are network keys, not passphrases
+        let key = key.map(|key| match cipher {
+            raw::Encryption::WEP => Key { kind: raw::KeyType::NetworkKey, ..key },
+            _ => key
+        });
+
+        Ok(Self {
             name: network.ssid.clone(),
             ssid: network.ssid,
+            non_broadcast: false,
             connection: Connection {
-                kind: raw::ConnectionType::from_bss(network.bss),
+                kind,
                 mode: raw::ConnectionMode::default()
             },
             auto_switch: Some(false),
             security: Security {
-                auth: raw::Authentication::from_network_auth(network.security.auth),
-                cipher: raw::Encryption::from_network_cipher(network.security.cipher),
-                key
+                auth,
+                cipher,
+                key,
+                enterprise: None
+            },
+            mac: Mac::default(),
+            source: None
+        })
+    }
+
+    /// ## Turn into a WPA-Enterprise profile
+    /// 
+    /// Drops the pre-shared key, since 802.1X
+    /// networks authenticate with EAP credentials.
+    /// Personal (or unknown, for hidden networks)
+    /// security is switched to its enterprise counterpart.
+    pub fn with_enterprise(mut self, enterprise: Enterprise) -> Self {
+        self.security.auth = match self.security.auth {
+            raw::Authentication::WPAPSK | raw::Authentication::WPA => raw::Authentication::WPA,
+            raw::Authentication::WPA3ENT => raw::Authentication::WPA3ENT,
+            raw::Authentication::WPA3ENT192 => raw::Authentication::WPA3ENT192,
+            _ => raw::Authentication::WPA2,
+        };
+        if let raw::Encryption::None | raw::Encryption::WEP = self.security.cipher {
+            self.security.cipher = raw::Encryption::AES
+        }
+        self.security.key = None;
+        self.security.enterprise = Some(enterprise);
+
+        self
+    }
+
+    /// ## Profile for a network that doesn't broadcast its SSID
+    /// 
+    /// Such network never shows up in the scan list,
+    /// so its security can't be learned from there
+    /// and has to be given.
+    pub fn from_hidden(
+        ssid: String,
+        key: Option<Key>,
+        auth: raw::Authentication,
+        cipher: raw::Encryption
+    ) -> Self {
+        Self {
+            name: ssid.clone(),
+            ssid,
+            non_broadcast: true,
+            connection: Connection {
+                kind: raw::ConnectionType::ESS,
+                mode: raw::ConnectionMode::default()
             },
-            mac: Mac::default()
+            auto_switch: Some(false),
+            security: Security { auth, cipher, key, enterprise: None },
+            mac: Mac::default(),
+            source: None
         }
     }
 }
+// --------- Getters ---------
+impl Profile {
+    pub fn is_enterprise(&self) -> bool {
+        self.security.enterprise.is_some()
+    }
+}
 // --------- Serialization ---------
 impl Profile {
     /// ## Serialize to `Writer` exactly how Windows does it
@@ -83,8 +232,9 @@ impl Profile {
     /// does not indent at all.
     ///
     /// This function provides such Windows-like config.
+    /// If the profile was parsed from XML, see `Profile::patches`.
     pub fn genuine_serialize<W: std::fmt::Write>(self, writer: &mut W) {
-        self.to_raw().genuine_serialize(writer)
+        writer.write_str(&self.genuine_serialize_to_string()).unwrap()
     }
 
     /// ## Serialize to `String` exactly how Windows does it
@@ -94,9 +244,141 @@ impl Profile {
     /// does not indent at all.
     ///
     /// This function provides such Windows-like config.
+    /// If the profile was parsed from XML, see `Profile::patches`.
     pub fn genuine_serialize_to_string(self) -> String {
+        if let Some(source) = self.source.as_ref() {
+            if let Some(patched) = self.patches().and_then(|patches| patch::apply(source, &patches).ok()) {
+                return patched
+            }
+            warn!(
+                "! PROFILE: could not patch the XML of {}, writing it from scratch (elements unknown to the app are dropped)",
+                self.name
+            );
+        }
+
         self.to_raw().genuine_serialize_to_string()
     }
+
+    /// ## Edits made since the profile was parsed
+    /// 
+    /// Only fields that differ from the source XML become
+    /// patches, so an unchanged profile serializes back
+    /// byte-for-byte, unknown elements included.
+    /// 
+    /// Returns `None` if there's no source or it can't be parsed.
+    pub fn patches(&self) -> Option<Vec<Patch>> {
+        const SSID: [&str; 3] = ["WLANProfile", "SSIDConfig", "SSID"];
+        const SECURITY: [&str; 3] = ["WLANProfile", "MSM", "security"];
+        const AUTH_ENCRYPTION: [&str; 4] = ["WLANProfile", "MSM", "security", "authEncryption"];
+        const SHARED_KEY: [&str; 4] = ["WLANProfile", "MSM", "security", "sharedKey"];
+
+        let original = raw::WLANProfile::deserialize_str(self.source.as_ref()?)
+            .ok()?
+            .to_friendly();
+        let mut patches = vec![];
+
+        if self.name != original.name {
+            patches.push(Patch::set_text(&["WLANProfile", "name"], &self.name))
+        }
+        if self.ssid != original.ssid {
+            patches.push(Patch::set_text(&[&SSID[..], &["hex"]].concat(), hex::encode_upper(&self.ssid)));
+            patches.push(Patch::set_text(&[&SSID[..], &["name"]].concat(), &self.ssid));
+        }
+        if self.non_broadcast != original.non_broadcast {
+            patches.push(if self.non_broadcast {
+                Patch::set_text(&["WLANProfile", "SSIDConfig", "nonBroadcast"], "true")
+            } else {
+                Patch::remove(&["WLANProfile", "SSIDConfig", "nonBroadcast"])
+            })
+        }
+        if self.connection.kind.to_string() != original.connection.kind.to_string() {
+            patches.push(Patch::set_text(&["WLANProfile", "connectionType"], &self.connection.kind))
+        }
+        if self.connection.mode.to_string() != original.connection.mode.to_string() {
+            patches.push(Patch::set_text(&["WLANProfile", "connectionMode"], &self.connection.mode))
+        }
+        if self.auto_switch != original.auto_switch {
+            patches.push(match self.auto_switch {
+                Some(auto_switch) => Patch::set_text(&["WLANProfile", "autoSwitch"], auto_switch),
+                None => Patch::remove(&["WLANProfile", "autoSwitch"])
+            })
+        }
+        if self.security.auth.to_string() != original.security.auth.to_string() {
+            patches.push(Patch::set_text(&[&AUTH_ENCRYPTION[..], &["authentication"]].concat(), &self.security.auth))
+        }
+        if self.security.cipher.to_string() != original.security.cipher.to_string() {
+            patches.push(Patch::set_text(&[&AUTH_ENCRYPTION[..], &["encryption"]].concat(), &self.security.cipher))
+        }
+        if self.security.key != original.security.key {
+            match self.security.key.as_ref() {
+                Some(key) => {
+                    patches.push(Patch::set_text(&[&SHARED_KEY[..], &["keyType"]].concat(), &key.kind));
+                    patches.push(Patch::set_text(&[&SHARED_KEY[..], &["protected"]].concat(), key.is_encrypted));
+                    patches.push(Patch::set_text(&[&SHARED_KEY[..], &["keyMaterial"]].concat(), &key.content));
+                },
+                None => patches.push(Patch::remove(&SHARED_KEY))
+            }
+        }
+        if self.security.enterprise != original.security.enterprise {
+            patches.push(Patch::set_text(
+                &[&AUTH_ENCRYPTION[..], &["useOneX"]].concat(),
+                self.security.enterprise.is_some()
+            ));
+            patches.push(match self.security.enterprise.as_ref() {
+                Some(enterprise) => Patch::replace(
+                    &[&SECURITY[..], &["OneX"]].concat(),
+                    quick_xml::se::to_string_with_root("OneX", &onex::OneX::from_enterprise(enterprise)).ok()?
+                ),
+                None => Patch::remove(&[&SECURITY[..], &["OneX"]].concat())
+            })
+        }
+        if self.mac.randomization != original.mac.randomization {
+            // keep `randomizationSeed` if the element is there
+            if self.source.as_ref()?.contains("<MacRandomization") {
+                patches.push(Patch::set_text(
+                    &["WLANProfile", "MacRandomization", "enableRandomization"],
+                    self.mac.randomization
+                ));
+                return Some(patches)
+            }
+            let mac = raw::MacRandomization {
+                xmlns: raw::XMLNS_PROFILE_V3.to_string(),
+                enable_randomization: self.mac.randomization
+            };
+            patches.push(Patch::replace(
+                &["WLANProfile", "MacRandomization"],
+                quick_xml::se::to_string_with_root("MacRandomization", &mac).ok()?
+            ))
+        }
+
+        Some(patches)
+    }
+}
+impl std::fmt::Debug for Profile {
+    /// Leaves `source` out, it has the key in it
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Profile")
+            .field("name", &self.name)
+            .field("ssid", &self.ssid)
+            .field("non_broadcast", &self.non_broadcast)
+            .field("connection", &self.connection)
+            .field("auto_switch", &self.auto_switch)
+            .field("security", &self.security)
+            .field("mac", &self.mac)
+            .field("source", &self.source.as_ref().map(|_| "..."))
+            .finish()
+    }
+}
+// --------- Redaction ---------
+impl Profile {
+    pub const REDACTED: &'static str = "********";
+
+    /// ## Hide the key, i.e. before showing the profile
+    pub fn redact_key(&mut self) {
+        if let Some(key) = self.security.key.as_mut() {
+            key.content = Self::REDACTED.to_string()
+        }
+    }
 }
 // --------- Constructors ---------
 impl Profile {
@@ -104,6 +386,7 @@ impl Profile {
         Self {
             name: raw.name,
             ssid: raw.ssid_config.ssid.name,
+            non_broadcast: raw.ssid_config.non_broadcast.unwrap_or(false),
             connection: Connection {
                 kind: raw.connection_type,
                 mode: raw.connection_mode
@@ -118,11 +401,15 @@ impl Profile {
                         is_encrypted: key.protected,
                         content: key.key_material
                     }
-                )
+                ),
+                enterprise: raw.msm.security.one_x.as_ref().map(
+                    |one_x| one_x.to_enterprise()
+                ).flatten()
             },
             mac: Mac {
                 randomization: raw.mac_randomization.enable_randomization
-            }
+            },
+            source: None
         }
     }
 }
@@ -132,3 +419,160 @@ impl Profile {
         raw::WLANProfile::from_friendly(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/profiles");
+
+    fn fixtures() -> Vec<(String, String)> {
+        let mut fixtures = std::fs::read_dir(FIXTURES).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().map(|ext| ext == "xml").unwrap_or(false))
+            .map(|path| (
+                path.file_name().unwrap().to_string_lossy().to_string(),
+                std::fs::read_to_string(&path).unwrap()
+            ))
+            .collect::<Vec<(String, String)>>();
+        fixtures.sort();
+        fixtures
+    }
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!("{}/{}", FIXTURES, name)).unwrap()
+    }
+
+    #[test]
+    fn round_trip_is_byte_for_byte() {
+        let fixtures = fixtures();
+        assert!(!fixtures.is_empty());
+
+        for (name, xml) in fixtures {
+            let profile = Profile::deserialize_str(&xml)
+                .unwrap_or_else(|err| panic!("{}: {}", name, err));
+            assert_eq!(profile.genuine_serialize_to_string(), xml, "{}", name);
+        }
+    }
+
+    #[test]
+    fn key_edit_keeps_unknown_elements() {
+        let xml = fixture("hidden-wpa3-transition.xml");
+        let mut profile = Profile::deserialize_str(&xml).unwrap();
+        profile.security.key = Some(Key::from_plain("n3w & s3cret"));
+
+        let patched = profile.genuine_serialize_to_string();
+        let expected = xml.replace(
+            "<keyMaterial>backup-modem-2024</keyMaterial>",
+            "<keyMaterial>n3w &amp; s3cret</keyMaterial>"
+        );
+        assert_eq!(patched, expected);
+    }
+
+    #[test]
+    fn missing_elements_are_inserted() {
+        let xml = fixture("connectivity-settings-no-mac.xml");
+        let mut profile = Profile::deserialize_str(&xml).unwrap();
+        profile.non_broadcast = true;
+        profile.mac.randomization = true;
+
+        let patched = profile.genuine_serialize_to_string();
+        assert!(patched.contains("</SSID>\n\t<nonBroadcast>true</nonBroadcast></SSIDConfig>"));
+        assert!(patched.contains("<hostedNetwork>false</hostedNetwork>"));
+        assert!(patched.contains("<enableRandomization>true</enableRandomization></MacRandomization></WLANProfile>"));
+
+        let reparsed = Profile::deserialize_str(&patched).unwrap();
+        assert!(reparsed.non_broadcast);
+        assert!(reparsed.mac.randomization);
+    }
+
+    #[test]
+    fn missing_elements_keep_schema_order() {
+        let xml = fixture("wpa2-enterprise-peap.xml");
+        let mut profile = Profile::deserialize_str(&xml).unwrap();
+        profile.security.auth = raw::Authentication::WPA2PSK;
+        profile.security.enterprise = None;
+        profile.security.key = Some(Key::from_plain("amogUSSR"));
+
+        let patched = profile.clone().genuine_serialize_to_string();
+        let auth_encryption = patched.find("</authEncryption>").unwrap();
+        let shared_key = patched.find("<sharedKey>").unwrap();
+        let pmk_cache = patched.find("<PMKCacheMode>").unwrap();
+        assert!(auth_encryption < shared_key && shared_key < pmk_cache, "{}", patched);
+        assert!(patched.contains("<sharedKey><keyType>passPhrase</keyType><protected>false</protected><keyMaterial>amogUSSR</keyMaterial></sharedKey>"));
+
+        let reparsed = Profile::deserialize_str(&patched).unwrap();
+        assert_eq!(reparsed.security.key, profile.security.key);
+    }
+
+    #[test]
+    fn unpatchable_source_is_written_from_scratch() {
+        let xml = fixture("wpa2-personal.xml");
+        let mut profile = Profile::deserialize_str(&xml).unwrap();
+        profile.source = Some("<WLANProfile><name>".to_string());
+        profile.security.key = Some(Key::from_plain("amogus"));
+
+        let written = Profile::deserialize_str(&profile.clone().genuine_serialize_to_string()).unwrap();
+        assert_eq!(written.name, profile.name);
+        assert_eq!(written.security.key, profile.security.key);
+    }
+
+    #[test]
+    fn protected_keys_match_by_plaintext() {
+        let protector = protector::Passthrough;
+        let plain = Key::from_plain("amogUSSR");
+        let protected = plain.protect(&protector).unwrap();
+
+        assert!(protected.is_encrypted);
+        assert!(protected.matches(&plain, &protector).unwrap());
+        assert!(!protected.matches(&Key::from_plain("amogus"), &protector).unwrap());
+    }
+
+    #[test]
+    fn debug_does_not_leak_keys() {
+        let xml = fixture("wpa2-personal.xml");
+        let profile = Profile::deserialize_str(&xml).unwrap();
+        let raw = raw::WLANProfile::deserialize_str(&xml).unwrap();
+
+        assert!(!format!("{:?}", profile).contains("amogUSSR"));
+        assert!(!format!("{:?}", raw).contains("amogUSSR"));
+    }
+
+    #[test]
+    fn enterprise_settings_round_trip() {
+        for method in [EapMethod::PeapMsChapV2, EapMethod::Ttls, EapMethod::Tls] {
+            let enterprise = Enterprise {
+                method,
+                server_names: Some("radius.corp.example".to_string()),
+                ca_thumbprints: vec!["A1B2C3D4E5F60718293A4B5C6D7E8F9012345678".to_string()],
+                certificate_thumbprint: None,
+                anonymous_identity: match method {
+                    EapMethod::Tls => None,
+                    _ => Some("anonymous@corp.example".to_string()),
+                },
+            };
+            let profile = Profile::from_hidden(
+                "Corp".to_string(),
+                None,
+                raw::Authentication::WPA2,
+                raw::Encryption::AES
+            ).with_enterprise(enterprise.clone());
+
+            let parsed = Profile::deserialize_str(&profile.genuine_serialize_to_string()).unwrap();
+            assert_eq!(parsed.security.enterprise, Some(enterprise), "{:?}", method);
+        }
+    }
+
+    #[test]
+    fn enterprise_removal_drops_one_x() {
+        let xml = fixture("wpa2-enterprise-peap.xml");
+        let mut profile = Profile::deserialize_str(&xml).unwrap();
+        assert_eq!(profile.security.enterprise.as_ref().map(|e| e.method), Some(EapMethod::PeapMsChapV2));
+        profile.security.enterprise = None;
+
+        let patched = profile.genuine_serialize_to_string();
+        assert!(!patched.contains("<OneX"));
+        assert!(patched.contains("<useOneX>false</useOneX>"));
+        assert!(patched.contains("<PMKCacheTTL>720</PMKCacheTTL>"));
+    }
+}
@@ -0,0 +1,581 @@
+//! ## 802.1X (WPA-Enterprise) part of a WI-FI profile
+//!
+//! Covers the `OneX` element of `MSM/security`
+//! and the EAP user credentials that are stored
+//! separately with `WlanSetProfileEapXmlUserData`.
+//!
+//! Supported methods:
+//! - PEAP with MSCHAPv2 inside
+//! - EAP-TTLS with MSCHAPv2 inside
+//! - EAP-TLS with a certificate from the user's store
+
+use serde_derive::{Serialize, Deserialize};
+
+
+pub const XMLNS_ONEX: &str = "http://www.microsoft.com/networking/OneX/v1";
+pub const XMLNS_EAP_HOST_CONFIG: &str = "http://www.microsoft.com/provisioning/EapHostConfig";
+pub const XMLNS_EAP_COMMON: &str = "http://www.microsoft.com/provisioning/EapCommon";
+pub const XMLNS_BASE_EAP: &str = "http://www.microsoft.com/provisioning/BaseEapConnectionPropertiesV1";
+pub const XMLNS_MS_PEAP: &str = "http://www.microsoft.com/provisioning/MsPeapConnectionPropertiesV1";
+pub const XMLNS_MS_PEAP_V2: &str = "http://www.microsoft.com/provisioning/MsPeapConnectionPropertiesV2";
+pub const XMLNS_MS_CHAP_V2: &str = "http://www.microsoft.com/provisioning/MsChapV2ConnectionPropertiesV1";
+pub const XMLNS_EAP_TTLS: &str = "http://www.microsoft.com/provisioning/EapTtlsConnectionPropertiesV1";
+pub const XMLNS_EAP_TLS: &str = "http://www.microsoft.com/provisioning/EapTlsConnectionPropertiesV1";
+pub const XMLNS_EAP_TLS_V2: &str = "http://www.microsoft.com/provisioning/EapTlsConnectionPropertiesV2";
+pub const XMLNS_EAP_TLS_V3: &str = "http://www.microsoft.com/provisioning/EapTlsConnectionPropertiesV3";
+
+pub const EAP_TYPE_TLS: u32 = 13;
+pub const EAP_TYPE_TTLS: u32 = 21;
+pub const EAP_TYPE_PEAP: u32 = 25;
+pub const EAP_TYPE_MS_CHAP_V2: u32 = 26;
+
+
+/// ## Element with its own `xmlns` and a text value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Namespaced<T> {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "$text")]
+    pub value: T,
+}
+impl<T> Namespaced<T> {
+    pub fn new(xmlns: &str, value: T) -> Self {
+        Self { xmlns: xmlns.to_string(), value }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EapMethod {
+    #[serde(rename = "Type")]
+    pub kind: Namespaced<u32>,
+    #[serde(rename = "VendorId")]
+    pub vendor_id: Namespaced<u32>,
+    #[serde(rename = "VendorType")]
+    pub vendor_type: Namespaced<u32>,
+    #[serde(rename = "AuthorId")]
+    pub author_id: Namespaced<u32>,
+}
+impl EapMethod {
+    pub fn from_type(kind: u32) -> Self {
+        Self {
+            kind: Namespaced::new(XMLNS_EAP_COMMON, kind),
+            vendor_id: Namespaced::new(XMLNS_EAP_COMMON, 0),
+            vendor_type: Namespaced::new(XMLNS_EAP_COMMON, 0),
+            author_id: Namespaced::new(XMLNS_EAP_COMMON, if kind == EAP_TYPE_TTLS { 311 } else { 0 }),
+        }
+    }
+}
+
+/// ## Server validation settings
+///
+/// PEAP and EAP-TLS use `DisableUserPromptForServerValidation`
+/// and `TrustedRootCA`, while EAP-TTLS uses `DisablePrompt`
+/// and `TrustedRootCAHash` for the same things.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerValidation {
+    #[serde(rename = "DisableUserPromptForServerValidation")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_user_prompt: Option<bool>,
+    #[serde(rename = "ServerNames")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_names: Option<String>,
+    #[serde(rename = "TrustedRootCA")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_root_ca: Vec<String>,
+    #[serde(rename = "TrustedRootCAHash")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_root_ca_hash: Vec<String>,
+    #[serde(rename = "DisablePrompt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_prompt: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateStore {
+    #[serde(rename = "SimpleCertSelection")]
+    pub simple_cert_selection: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialsSource {
+    #[serde(rename = "CertificateStore")]
+    pub certificate_store: CertificateStore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdentityPrivacy {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "EnableIdentityPrivacy")]
+    pub enable_identity_privacy: bool,
+    #[serde(rename = "AnonymousUserName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anonymous_user_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeapExtensions {
+    #[serde(rename = "PerformServerValidation")]
+    pub perform_server_validation: Namespaced<bool>,
+    #[serde(rename = "AcceptServerName")]
+    pub accept_server_name: Namespaced<bool>,
+    /// ## Outer identity sent instead of the real one
+    #[serde(rename = "IdentityPrivacy")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_privacy: Option<IdentityPrivacy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashList {
+    #[serde(rename = "@Enabled")]
+    pub enabled: bool,
+    #[serde(rename = "IssuerHash")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issuer_hash: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteringInfo {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "CAHashList")]
+    pub ca_hash_list: HashList,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsExtensions {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "FilteringInfo")]
+    pub filtering_info: FilteringInfo,
+}
+
+/// ## Method-specific properties
+///
+/// The shape depends on the `xmlns`, so everything
+/// not shared between PEAP, MSCHAPv2 and EAP-TLS
+/// is optional.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EapType {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "CredentialsSource")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credentials_source: Option<CredentialsSource>,
+    #[serde(rename = "ServerValidation")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_validation: Option<ServerValidation>,
+    #[serde(rename = "FastReconnect")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fast_reconnect: Option<bool>,
+    #[serde(rename = "InnerEapOptional")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inner_eap_optional: Option<bool>,
+    #[serde(rename = "Eap")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eap: Option<Box<Eap>>,
+    #[serde(rename = "EnableQuarantineChecks")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_quarantine_checks: Option<bool>,
+    #[serde(rename = "RequireCryptoBinding")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_crypto_binding: Option<bool>,
+    #[serde(rename = "PeapExtensions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peap_extensions: Option<PeapExtensions>,
+    #[serde(rename = "UseWinLogonCredentials")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_win_logon_credentials: Option<bool>,
+    #[serde(rename = "DifferentUsername")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub different_username: Option<bool>,
+    #[serde(rename = "PerformServerValidation")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub perform_server_validation: Option<Namespaced<bool>>,
+    #[serde(rename = "AcceptServerName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_server_name: Option<Namespaced<bool>>,
+    #[serde(rename = "TLSExtensions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_extensions: Option<TlsExtensions>,
+}
+impl EapType {
+    fn empty(xmlns: &str) -> Self {
+        Self {
+            xmlns: xmlns.to_string(),
+            credentials_source: None,
+            server_validation: None,
+            fast_reconnect: None,
+            inner_eap_optional: None,
+            eap: None,
+            enable_quarantine_checks: None,
+            require_crypto_binding: None,
+            peap_extensions: None,
+            use_win_logon_credentials: None,
+            different_username: None,
+            perform_server_validation: None,
+            accept_server_name: None,
+            tls_extensions: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Eap {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "Type")]
+    pub kind: u32,
+    #[serde(rename = "EapType")]
+    pub eap_type: EapType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Phase1Identity {
+    #[serde(rename = "IdentityPrivacy")]
+    pub identity_privacy: bool,
+    #[serde(rename = "AnonymousIdentity")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anonymous_identity: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MsChapV2Authentication {
+    #[serde(rename = "UseWinlogonCredentials")]
+    pub use_winlogon_credentials: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Phase2Authentication {
+    #[serde(rename = "MSCHAPv2Authentication")]
+    pub ms_chap_v2: MsChapV2Authentication,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EapTtls {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "ServerValidation")]
+    pub server_validation: ServerValidation,
+    #[serde(rename = "Phase2Authentication")]
+    pub phase2_authentication: Phase2Authentication,
+    #[serde(rename = "Phase1Identity")]
+    pub phase1_identity: Phase1Identity,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "Eap")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eap: Option<Eap>,
+    #[serde(rename = "EapTtls")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eap_ttls: Option<EapTtls>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EapHostConfig {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "EapMethod")]
+    pub eap_method: EapMethod,
+    #[serde(rename = "Config")]
+    pub config: Config,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EAPConfig {
+    #[serde(rename = "EapHostConfig")]
+    pub eap_host_config: EapHostConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OneX {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    #[serde(rename = "authMode")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_mode: Option<String>,
+    #[serde(rename = "EAPConfig")]
+    pub eap_config: EAPConfig,
+}
+// --------- Constructors ---------
+impl OneX {
+    fn wrap(kind: u32, config: Config) -> Self {
+        Self {
+            xmlns: XMLNS_ONEX.to_string(),
+            auth_mode: Some("user".to_string()),
+            eap_config: EAPConfig {
+                eap_host_config: EapHostConfig {
+                    xmlns: XMLNS_EAP_HOST_CONFIG.to_string(),
+                    eap_method: EapMethod::from_type(kind),
+                    config
+                }
+            }
+        }
+    }
+
+    fn server_validation(enterprise: &super::Enterprise) -> ServerValidation {
+        ServerValidation {
+            disable_user_prompt: Some(true),
+            server_names: enterprise.server_names.clone(),
+            trusted_root_ca: enterprise.ca_thumbprints.iter()
+                .map(|thumbprint| thumbprint_to_xml(thumbprint))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn peap_ms_chap_v2(enterprise: &super::Enterprise) -> Self {
+        let mut ms_chap_v2 = EapType::empty(XMLNS_MS_CHAP_V2);
+        ms_chap_v2.use_win_logon_credentials = Some(false);
+
+        let mut peap = EapType::empty(XMLNS_MS_PEAP);
+        peap.server_validation = Some(Self::server_validation(enterprise));
+        peap.fast_reconnect = Some(true);
+        peap.inner_eap_optional = Some(false);
+        peap.eap = Some(Box::new(Eap {
+            xmlns: XMLNS_BASE_EAP.to_string(),
+            kind: EAP_TYPE_MS_CHAP_V2,
+            eap_type: ms_chap_v2
+        }));
+        peap.enable_quarantine_checks = Some(false);
+        peap.require_crypto_binding = Some(false);
+        peap.peap_extensions = Some(PeapExtensions {
+            perform_server_validation: Namespaced::new(XMLNS_MS_PEAP_V2, true),
+            accept_server_name: Namespaced::new(XMLNS_MS_PEAP_V2, enterprise.server_names.is_some()),
+            identity_privacy: enterprise.anonymous_identity.as_ref().map(|anonymous| IdentityPrivacy {
+                xmlns: XMLNS_MS_PEAP_V2.to_string(),
+                enable_identity_privacy: true,
+                anonymous_user_name: Some(anonymous.clone())
+            }),
+        });
+
+        Self::wrap(EAP_TYPE_PEAP, Config {
+            xmlns: XMLNS_EAP_HOST_CONFIG.to_string(),
+            eap: Some(Eap {
+                xmlns: XMLNS_BASE_EAP.to_string(),
+                kind: EAP_TYPE_PEAP,
+                eap_type: peap
+            }),
+            eap_ttls: None
+        })
+    }
+
+    pub fn ttls(enterprise: &super::Enterprise) -> Self {
+        let ttls = EapTtls {
+            xmlns: XMLNS_EAP_TTLS.to_string(),
+            server_validation: ServerValidation {
+                server_names: enterprise.server_names.clone(),
+                trusted_root_ca_hash: enterprise.ca_thumbprints.iter()
+                    .map(|thumbprint| thumbprint_to_xml(thumbprint))
+                    .collect(),
+                disable_prompt: Some(true),
+                ..Default::default()
+            },
+            phase2_authentication: Phase2Authentication {
+                ms_chap_v2: MsChapV2Authentication { use_winlogon_credentials: false }
+            },
+            phase1_identity: Phase1Identity {
+                identity_privacy: enterprise.anonymous_identity.is_some(),
+                anonymous_identity: enterprise.anonymous_identity.clone()
+            }
+        };
+
+        Self::wrap(EAP_TYPE_TTLS, Config {
+            xmlns: XMLNS_EAP_HOST_CONFIG.to_string(),
+            eap: None,
+            eap_ttls: Some(ttls)
+        })
+    }
+
+    pub fn tls(enterprise: &super::Enterprise) -> Self {
+        let mut tls = EapType::empty(XMLNS_EAP_TLS);
+        tls.credentials_source = Some(CredentialsSource {
+            certificate_store: CertificateStore { simple_cert_selection: true }
+        });
+        tls.server_validation = Some(Self::server_validation(enterprise));
+        tls.different_username = Some(false);
+        tls.perform_server_validation = Some(Namespaced::new(XMLNS_EAP_TLS_V2, true));
+        tls.accept_server_name = Some(Namespaced::new(XMLNS_EAP_TLS_V2, enterprise.server_names.is_some()));
+        tls.tls_extensions = enterprise.certificate_thumbprint.as_ref().map(|thumbprint| TlsExtensions {
+            xmlns: XMLNS_EAP_TLS_V2.to_string(),
+            filtering_info: FilteringInfo {
+                xmlns: XMLNS_EAP_TLS_V3.to_string(),
+                ca_hash_list: HashList {
+                    enabled: true,
+                    issuer_hash: vec![thumbprint_to_xml(thumbprint)]
+                }
+            }
+        });
+
+        Self::wrap(EAP_TYPE_TLS, Config {
+            xmlns: XMLNS_EAP_HOST_CONFIG.to_string(),
+            eap: Some(Eap {
+                xmlns: XMLNS_BASE_EAP.to_string(),
+                kind: EAP_TYPE_TLS,
+                eap_type: tls
+            }),
+            eap_ttls: None
+        })
+    }
+
+    pub fn from_enterprise(enterprise: &super::Enterprise) -> Self {
+        match enterprise.method {
+            super::EapMethod::PeapMsChapV2 => Self::peap_ms_chap_v2(enterprise),
+            super::EapMethod::Ttls => Self::ttls(enterprise),
+            super::EapMethod::Tls => Self::tls(enterprise),
+        }
+    }
+}
+// --------- Converters ---------
+impl OneX {
+    /// ## Convert to the friendly form
+    ///
+    /// Returns `None` for EAP methods other than
+    /// the supported ones.
+    pub fn to_enterprise(&self) -> Option<super::Enterprise> {
+        let host = &self.eap_config.eap_host_config;
+        let config = &host.config;
+
+        let (method, validation) = match host.eap_method.kind.value {
+            EAP_TYPE_PEAP => (
+                super::EapMethod::PeapMsChapV2,
+                config.eap.as_ref()?.eap_type.server_validation.clone()
+            ),
+            EAP_TYPE_TLS => (
+                super::EapMethod::Tls,
+                config.eap.as_ref()?.eap_type.server_validation.clone()
+            ),
+            EAP_TYPE_TTLS => (
+                super::EapMethod::Ttls,
+                config.eap_ttls.as_ref().map(|ttls| ttls.server_validation.clone())
+            ),
+            _ => return None
+        };
+        let validation = validation.unwrap_or_default();
+
+        let ca_thumbprints = validation.trusted_root_ca.iter()
+            .chain(validation.trusted_root_ca_hash.iter())
+            .map(|thumbprint| thumbprint_from_xml(thumbprint))
+            .collect();
+
+        // PEAP also sends it as `RoutingIdentity` of the user
+        // credentials, but those can't be read back
+        let anonymous_identity = match method {
+            super::EapMethod::Ttls => config.eap_ttls.as_ref()
+                .and_then(|ttls| ttls.phase1_identity.anonymous_identity.clone()),
+            super::EapMethod::PeapMsChapV2 => config.eap.as_ref()
+                .and_then(|eap| eap.eap_type.peap_extensions.as_ref())
+                .and_then(|ext| ext.identity_privacy.as_ref())
+                .filter(|privacy| privacy.enable_identity_privacy)
+                .and_then(|privacy| privacy.anonymous_user_name.clone()),
+            super::EapMethod::Tls => None,
+        };
+
+        let certificate_thumbprint = config.eap.as_ref()
+            .map(|eap| eap.eap_type.tls_extensions.as_ref())
+            .flatten()
+            .map(|ext| ext.filtering_info.ca_hash_list.issuer_hash.first().cloned())
+            .flatten()
+            .map(|thumbprint| thumbprint_from_xml(&thumbprint));
+
+        Some(super::Enterprise {
+            method,
+            server_names: validation.server_names,
+            ca_thumbprints,
+            certificate_thumbprint,
+            anonymous_identity,
+        })
+    }
+}
+
+
+/// ## Thumbprint as Windows writes it in profiles
+///
+/// `A1B2C3...`, `a1:b2:c3...` and `a1 b2 c3...`
+/// all become `a1 b2 c3 ...`.
+pub fn thumbprint_to_xml(thumbprint: &str) -> String {
+    let hex = thumbprint.chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect::<String>()
+        .to_lowercase();
+
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| String::from_utf8_lossy(pair).to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// ## Thumbprint as it's usually shown (`A1B2C3...`)
+pub fn thumbprint_from_xml(thumbprint: &str) -> String {
+    thumbprint.chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn escape(string: &str) -> String {
+    quick_xml::escape::escape(string).to_string()
+}
+
+/// ## EAP user credentials for `WlanSetProfileEapXmlUserData`
+///
+/// Only PEAP and EAP-TTLS with MSCHAPv2 use
+/// a username and a password. EAP-TLS takes
+/// the certificate from the store, so `None`
+/// is returned for it.
+pub fn user_credentials_xml(
+    enterprise: &super::Enterprise,
+    identity: &str,
+    password: &str,
+) -> Option<String> {
+    let identity = escape(identity);
+    let password = escape(password);
+    let routing_identity = enterprise.anonymous_identity.as_ref()
+        .map(|anon| escape(anon))
+        .unwrap_or_default();
+
+    match enterprise.method {
+        super::EapMethod::PeapMsChapV2 => Some(format!(
+            concat!(
+                "<EapHostUserCredentials xmlns=\"http://www.microsoft.com/provisioning/EapHostUserCredentials\" ",
+                "xmlns:eapCommon=\"http://www.microsoft.com/provisioning/EapCommon\" ",
+                "xmlns:baseEap=\"http://www.microsoft.com/provisioning/BaseEapMethodUserCredentials\">",
+                "<EapMethod><eapCommon:Type>25</eapCommon:Type><eapCommon:AuthorId>0</eapCommon:AuthorId></EapMethod>",
+                "<Credentials xmlns:eapUser=\"http://www.microsoft.com/provisioning/EapUserPropertiesV1\" ",
+                "xmlns:MsPeap=\"http://www.microsoft.com/provisioning/MsPeapUserPropertiesV1\" ",
+                "xmlns:MsChapV2=\"http://www.microsoft.com/provisioning/MsChapV2UserPropertiesV1\">",
+                "<baseEap:Eap><baseEap:Type>25</baseEap:Type><MsPeap:EapType>",
+                "<MsPeap:RoutingIdentity>{}</MsPeap:RoutingIdentity>",
+                "<baseEap:Eap><baseEap:Type>26</baseEap:Type><MsChapV2:EapType>",
+                "<MsChapV2:Username>{}</MsChapV2:Username>",
+                "<MsChapV2:Password>{}</MsChapV2:Password>",
+                "<MsChapV2:LogonDomain></MsChapV2:LogonDomain>",
+                "</MsChapV2:EapType></baseEap:Eap>",
+                "</MsPeap:EapType></baseEap:Eap>",
+                "</Credentials></EapHostUserCredentials>"
+            ),
+            routing_identity, identity, password
+        )),
+        super::EapMethod::Ttls => Some(format!(
+            concat!(
+                "<EapHostUserCredentials xmlns=\"http://www.microsoft.com/provisioning/EapHostUserCredentials\" ",
+                "xmlns:eapCommon=\"http://www.microsoft.com/provisioning/EapCommon\" ",
+                "xmlns:baseEap=\"http://www.microsoft.com/provisioning/BaseEapMethodUserCredentials\">",
+                "<EapMethod><eapCommon:Type>21</eapCommon:Type><eapCommon:AuthorId>311</eapCommon:AuthorId></EapMethod>",
+                "<Credentials>",
+                "<EapTtls xmlns=\"http://www.microsoft.com/provisioning/EapTtlsUserPropertiesV1\">",
+                "<Username>{}</Username>",
+                "<Password>{}</Password>",
+                "</EapTtls>",
+                "</Credentials></EapHostUserCredentials>"
+            ),
+            identity, password
+        )),
+        super::EapMethod::Tls => None
+    }
+}
@@ -0,0 +1,313 @@
+//! ## Targeted edits of a profile's XML
+//!
+//! Profiles returned by Windows may have elements
+//! `raw::WLANProfile` knows nothing about (connectivity
+//! settings, randomization seed, `transitionMode`, etc.).
+//! Instead of re-serializing the whole profile and
+//! dropping them, edits are applied right to the
+//! original XML, leaving everything else byte-for-byte
+//! the same.
+//!
+//! Missing elements are inserted at their place in the
+//! `WLANProfile` schema, since Windows rejects profiles
+//! with elements out of order.
+
+use quick_xml::events::{Event, BytesText};
+use quick_xml::{Reader, Writer};
+
+
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// ## Set the text of an element
+    /// Creates the element (and missing parents) if needed.
+    SetText(String),
+    /// ## Replace the whole element with an XML fragment
+    /// Inserts the fragment if the element is missing.
+    Replace(String),
+    /// ## Remove the element with everything inside
+    Remove,
+}
+
+#[derive(Debug, Clone)]
+pub struct Patch {
+    /// ## Local names from the root, i.e. `["WLANProfile", "MSM", "security"]`
+    pub path: Vec<String>,
+    pub edit: Edit,
+}
+impl Patch {
+    pub fn set_text(path: &[&str], text: impl ToString) -> Self {
+        Self {
+            path: path.iter().map(|s| s.to_string()).collect(),
+            edit: Edit::SetText(text.to_string())
+        }
+    }
+
+    pub fn replace(path: &[&str], fragment: impl ToString) -> Self {
+        Self {
+            path: path.iter().map(|s| s.to_string()).collect(),
+            edit: Edit::Replace(fragment.to_string())
+        }
+    }
+
+    pub fn remove(path: &[&str]) -> Self {
+        Self {
+            path: path.iter().map(|s| s.to_string()).collect(),
+            edit: Edit::Remove
+        }
+    }
+
+    fn local_name(&self) -> &str {
+        self.path.last().map(|s| s.as_str()).unwrap_or("")
+    }
+}
+
+struct Level {
+    name: String,
+    seen: Vec<String>,
+}
+
+fn is_path(stack: &[Level], path: &[String]) -> bool {
+    stack.len() == path.len()
+    && stack.iter().zip(path.iter()).all(|(level, name)| &level.name == name)
+}
+
+fn is_under(stack: &[Level], path: &[String]) -> bool {
+    path.len() > stack.len()
+    && stack.iter().zip(path.iter()).all(|(level, name)| &level.name == name)
+}
+
+fn local_name_of(raw: &[u8]) -> String {
+    let name = String::from_utf8_lossy(raw).to_string();
+    name.rsplit(':').next().unwrap_or(&name).to_string()
+}
+
+fn escape(text: &str) -> String {
+    quick_xml::escape::escape(text).to_string()
+}
+
+/// ## Order of the children of `parent`, as the schema wants it
+fn schema_order(parent: &str) -> &'static [&'static str] {
+    match parent {
+        "WLANProfile" => &[
+            "name", "SSIDConfig", "connectionType", "connectionMode",
+            "autoSwitch", "MSM", "IHV", "MacRandomization"
+        ],
+        "SSIDConfig" => &["SSID", "nonBroadcast"],
+        "SSID" => &["hex", "name"],
+        "MSM" => &["connectivity", "security"],
+        "security" => &[
+            "authEncryption", "sharedKey", "keyIndex", "PMKCacheMode", "PMKCacheTTL",
+            "PMKCacheSize", "preAuthMode", "preAuthThrottle", "OneX"
+        ],
+        "authEncryption" => &["authentication", "encryption", "useOneX", "FIPSMode", "transitionMode"],
+        "sharedKey" => &["keyType", "protected", "keyMaterial"],
+        "MacRandomization" => &["enableRandomization", "randomizationSeed"],
+        _ => &[]
+    }
+}
+
+fn schema_position(parent: &str, child: &str) -> Option<usize> {
+    schema_order(parent).iter().position(|name| *name == child)
+}
+
+/// ## Write missing children of the current element
+///
+/// With `before`, only the ones the schema puts before
+/// that child are written. Children of unknown position
+/// only go at the end.
+fn insert_missing(
+    writer: &mut Writer<Vec<u8>>,
+    stack: &[Level],
+    patches: &[Patch],
+    applied: &mut [bool],
+    before: Option<&str>
+) {
+    let Some(parent) = stack.last() else {
+        return
+    };
+    let before = match before.map(|child| schema_position(&parent.name, child)) {
+        Some(Some(position)) => Some(position),
+        // nothing is known to go before an unknown element
+        Some(None) => return,
+        None => None
+    };
+
+    let missing = patches.iter().enumerate()
+        .filter(|(idx, patch)| !applied[*idx] && is_under(stack, &patch.path))
+        .filter(|(_, patch)| !parent.seen.contains(&patch.path[stack.len()]))
+        .filter(|(_, patch)| match before {
+            Some(before) => schema_position(&parent.name, &patch.path[stack.len()])
+                .map(|position| position < before)
+                .unwrap_or(false),
+            None => true
+        })
+        .collect::<Vec<(usize, &Patch)>>();
+
+    if missing.is_empty() {
+        return
+    }
+
+    let missing_patches = missing.iter().map(|(_, patch)| *patch).collect::<Vec<&Patch>>();
+    let fragment = missing_fragment(&missing_patches, stack.len());
+    writer.get_mut().extend_from_slice(fragment.as_bytes());
+
+    for (idx, _) in missing.iter() {
+        applied[*idx] = true;
+    }
+}
+
+/// ## Build elements for patches whose target is missing
+///
+/// `depth` is the index in `path` of the first missing element.
+fn missing_fragment(patches: &[&Patch], depth: usize) -> String {
+    let mut fragment = String::new();
+    let mut names = vec![];
+
+    for patch in patches.iter() {
+        let name = &patch.path[depth];
+        if !names.contains(name) {
+            names.push(name.clone())
+        }
+    }
+    if let Some(parent) = patches.first().map(|patch| &patch.path[depth - 1]) {
+        names.sort_by_key(|name| schema_position(parent, name).unwrap_or(usize::MAX));
+    }
+
+    for name in names.iter() {
+        let children = patches.iter()
+            .filter(|patch| &patch.path[depth] == name)
+            .copied()
+            .collect::<Vec<&Patch>>();
+
+        let leaf = children.iter().find(|patch| patch.path.len() == depth + 1);
+
+        match leaf.map(|patch| &patch.edit) {
+            Some(Edit::SetText(text)) => {
+                fragment.push_str(&format!("<{}>{}</{}>", name, escape(text), name))
+            },
+            Some(Edit::Replace(xml)) => fragment.push_str(xml),
+            Some(Edit::Remove) => (),
+            None => {
+                let deeper = children.iter()
+                    .filter(|patch| patch.path.len() > depth + 1)
+                    .copied()
+                    .collect::<Vec<&Patch>>();
+                let inner = missing_fragment(&deeper, depth + 1);
+                if !inner.is_empty() {
+                    fragment.push_str(&format!("<{}>{}</{}>", name, inner, name))
+                }
+            }
+        }
+    }
+
+    fragment
+}
+
+/// ## Apply patches to the XML
+///
+/// With no patches, the output is exactly the input.
+pub fn apply(source: &str, patches: &[Patch]) -> Result<String, quick_xml::Error> {
+    let mut reader = Reader::from_str(source);
+    reader.trim_text(false);
+    let mut writer = Writer::new(Vec::<u8>::new());
+
+    let mut stack: Vec<Level> = vec![];
+    let mut applied = vec![false; patches.len()];
+    // depth of the element being skipped (removed or replaced)
+    let mut skipping: Option<usize> = None;
+    // patch whose text goes into the current element
+    let mut texting: Option<usize> = None;
+
+    loop {
+        let event = reader.read_event()?;
+
+        if let Some(depth) = skipping {
+            match &event {
+                Event::Start(start) => stack.push(Level {
+                    name: local_name_of(start.name().as_ref()),
+                    seen: vec![]
+                }),
+                Event::End(_) => {
+                    stack.pop();
+                    if stack.len() == depth {
+                        skipping = None;
+                    }
+                },
+                Event::Eof => break,
+                _ => ()
+            }
+            continue;
+        }
+
+        match &event {
+            Event::Start(start) | Event::Empty(start) => {
+                let name = local_name_of(start.name().as_ref());
+                let is_empty = matches!(event, Event::Empty(_));
+
+                insert_missing(&mut writer, &stack, patches, &mut applied, Some(&name));
+                if let Some(parent) = stack.last_mut() {
+                    parent.seen.push(name.clone());
+                }
+                stack.push(Level { name, seen: vec![] });
+
+                let matching = patches.iter().position(|patch| is_path(&stack, &patch.path));
+
+                match matching.map(|idx| (idx, &patches[idx].edit)) {
+                    Some((idx, Edit::Remove)) | Some((idx, Edit::Replace(_))) => {
+                        applied[idx] = true;
+                        if let Edit::Replace(xml) = &patches[idx].edit {
+                            writer.get_mut().extend_from_slice(xml.as_bytes());
+                        }
+                        if is_empty {
+                            stack.pop();
+                        } else {
+                            skipping = Some(stack.len() - 1);
+                        }
+                        continue;
+                    },
+                    Some((idx, Edit::SetText(text))) => {
+                        applied[idx] = true;
+                        if is_empty {
+                            let name = patches[idx].local_name().to_string();
+                            writer.get_mut().extend_from_slice(
+                                format!("<{}>{}</{}>", name, escape(text), name).as_bytes()
+                            );
+                            stack.pop();
+                            continue;
+                        }
+                        writer.write_event(&event)?;
+                        texting = Some(idx);
+                        continue;
+                    },
+                    None => ()
+                }
+
+                if is_empty {
+                    stack.pop();
+                }
+            },
+            Event::Text(_) | Event::CData(_) if texting.is_some() => {
+                continue;
+            },
+            Event::End(_) => {
+                if let Some(idx) = texting.take() {
+                    if let Edit::SetText(text) = &patches[idx].edit {
+                        writer.write_event(Event::Text(BytesText::new(text)))?;
+                    }
+                    writer.write_event(&event)?;
+                    stack.pop();
+                    continue;
+                }
+
+                insert_missing(&mut writer, &stack, patches, &mut applied, None);
+                stack.pop();
+            },
+            Event::Eof => break,
+            _ => ()
+        }
+
+        writer.write_event(&event)?;
+    }
+
+    Ok(String::from_utf8_lossy(&writer.into_inner()).to_string())
+}
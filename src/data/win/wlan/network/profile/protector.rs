@@ -0,0 +1,122 @@
+//! ## Protection of key material
+//!
+//! Windows stores profile keys encrypted with DPAPI
+//! (`<protected>true</protected>`). The same has to be
+//! done to store a key protected, or to compare
+//! a protected key with the config.
+
+use std::fmt;
+
+
+#[derive(Debug, Clone)]
+pub enum ProtectError {
+    /// ## `CryptProtectData` or `CryptUnprotectData` failed
+    Native(u32),
+    /// ## Protected key is not a valid hex string
+    Hex,
+    /// ## Unprotected key is not valid UTF-8
+    Utf8,
+}
+impl fmt::Display for ProtectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Native(code) => write!(f, "DPAPI call failed with code {}", code),
+            Self::Hex => write!(f, "protected key is not a valid hex string"),
+            Self::Utf8 => write!(f, "unprotected key is not valid UTF-8"),
+        }
+    }
+}
+
+pub trait KeyProtector: Send + Sync {
+    /// ## Turn a plaintext key into `keyMaterial` of a protected key
+    fn protect(&self, plain: &str) -> Result<String, ProtectError>;
+    /// ## Turn `keyMaterial` of a protected key into plaintext
+    fn unprotect(&self, protected: &str) -> Result<String, ProtectError>;
+}
+
+/// ## DPAPI with the machine scope, like Windows does
+///
+/// Key material is the hex of the DPAPI blob.
+#[cfg(windows)]
+pub struct Dpapi;
+#[cfg(windows)]
+impl Dpapi {
+    fn call(data: &[u8], protect: bool) -> Result<Vec<u8>, ProtectError> {
+        use windows::Win32::Foundation::{GetLastError, HLOCAL};
+        use windows::Win32::Security::Cryptography::{
+            CryptProtectData,
+            CryptUnprotectData,
+            CRYPT_INTEGER_BLOB,
+            CRYPTPROTECT_LOCAL_MACHINE,
+            CRYPTPROTECT_UI_FORBIDDEN
+        };
+        use windows::Win32::System::Memory::LocalFree;
+
+        let input = CRYPT_INTEGER_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8
+        };
+        let mut output = CRYPT_INTEGER_BLOB { cbData: 0, pbData: std::ptr::null_mut() };
+        let flags = CRYPTPROTECT_LOCAL_MACHINE | CRYPTPROTECT_UI_FORBIDDEN;
+
+        let ok = unsafe {
+            if protect {
+                CryptProtectData(&input, None, None, None, None, flags, &mut output)
+            } else {
+                CryptUnprotectData(&input, None, None, None, None, flags, &mut output)
+            }
+        };
+
+        if !ok.as_bool() {
+            return Err(ProtectError::Native(unsafe { GetLastError() }.0))
+        }
+
+        let result = unsafe {
+            std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec()
+        };
+        unsafe { let _ = LocalFree(HLOCAL(output.pbData as isize)); };
+
+        Ok(result)
+    }
+}
+#[cfg(windows)]
+impl KeyProtector for Dpapi {
+    fn protect(&self, plain: &str) -> Result<String, ProtectError> {
+        Self::call(plain.as_bytes(), true).map(hex::encode_upper)
+    }
+
+    fn unprotect(&self, protected: &str) -> Result<String, ProtectError> {
+        let blob = hex::decode(protected.trim()).map_err(|_| ProtectError::Hex)?;
+        let plain = Self::call(&blob, false)?;
+
+        // Windows stores the key with a trailing NUL
+        String::from_utf8(plain)
+            .map(|plain| plain.trim_end_matches('\0').to_string())
+            .map_err(|_| ProtectError::Utf8)
+    }
+}
+
+/// ## No protection at all
+///
+/// "Protected" keys are the same as plaintext ones.
+/// Used where DPAPI isn't available, and in tests.
+pub struct Passthrough;
+impl KeyProtector for Passthrough {
+    fn protect(&self, plain: &str) -> Result<String, ProtectError> {
+        Ok(plain.to_string())
+    }
+
+    fn unprotect(&self, protected: &str) -> Result<String, ProtectError> {
+        Ok(protected.to_string())
+    }
+}
+
+/// ## Protector of the current platform
+pub fn platform() -> Box<dyn KeyProtector> {
+    #[cfg(windows)] {
+        Box::new(Dpapi)
+    }
+    #[cfg(not(windows))] {
+        Box::new(Passthrough)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::data::win::wlan::network::Bss;
 use crate::data::win::wlan::network;
+use super::UnsupportedSecurity;
 
 use serde::Serialize as SerializeTrait;
 use serde_derive::{Serialize, Deserialize};
@@ -33,13 +34,21 @@ pub enum Authentication {
     OWE,
 }
 impl Authentication {
-    pub fn from_network_auth(auth: network::Authentication) -> Self {
+    pub fn from_network_auth(auth: network::Authentication) -> Result<Self, UnsupportedSecurity> {
         match auth {
-            network::Authentication::Open => Self::Open,
-            network::Authentication::WpaPsk => Self::WPAPSK,
-            network::Authentication::RsnaPsk => Self::WPA2PSK,
-            network::Authentication::Wpa3Sae => Self::WPA3SAE,
-            _ => unimplemented!()
+            network::Authentication::Open => Ok(Self::Open),
+            network::Authentication::SharedKey => Ok(Self::Shared),
+            network::Authentication::Wpa => Ok(Self::WPA),
+            network::Authentication::WpaPsk => Ok(Self::WPAPSK),
+            network::Authentication::Rsna => Ok(Self::WPA2),
+            network::Authentication::RsnaPsk => Ok(Self::WPA2PSK),
+            network::Authentication::Wpa3Sae => Ok(Self::WPA3SAE),
+            network::Authentication::Wpa3 => Ok(Self::WPA3ENT192),
+            network::Authentication::Wpa3Ent => Ok(Self::WPA3ENT),
+            network::Authentication::Owe => Ok(Self::OWE),
+            network::Authentication::WpaNone
+            | network::Authentication::IhvStart
+            | network::Authentication::IhvEnd => Err(UnsupportedSecurity::Authentication(auth)),
         }
     }
 }
@@ -53,20 +62,70 @@ pub enum Encryption {
     WEP,
     TKIP,
     AES,
+    GCMP,
     GCMP256
 }
 impl Encryption {
-    pub fn from_network_cipher(cipher: network::Encryption) -> Self {
+    pub fn from_network_cipher(cipher: network::Encryption) -> Result<Self, UnsupportedSecurity> {
         match cipher {
-            network::Encryption::None => Self::None,
-            network::Encryption::Ccmp => Self::AES,
-            network::Encryption::Wep => Self::WEP,
-            _ => unimplemented!()
+            network::Encryption::None => Ok(Self::None),
+            network::Encryption::Wep
+            | network::Encryption::Wep40
+            | network::Encryption::Wep104 => Ok(Self::WEP),
+            network::Encryption::Tkip => Ok(Self::TKIP),
+            network::Encryption::Ccmp => Ok(Self::AES),
+            network::Encryption::Gcmp => Ok(Self::GCMP),
+            network::Encryption::Gcmp256 => Ok(Self::GCMP256),
+            network::Encryption::Ccmp256
+            | network::Encryption::Bip
+            | network::Encryption::BipGmac128
+            | network::Encryption::BipGmac256
+            | network::Encryption::BipCmac256
+            | network::Encryption::WpaUseGroup
+            | network::Encryption::RsnUseGroup
+            | network::Encryption::IhvStart
+            | network::Encryption::IhvEnd => Err(UnsupportedSecurity::Encryption(cipher)),
         }
     }
 }
 
-#[derive(Debug, Clone, EnumString, Display)]
+/// ## Map scanned security to the profile one
+/// 
+/// Besides converting authentication and encryption
+/// separately, checks that the pair makes sense
+/// for a profile (i.e. WEP only goes with open
+/// or shared authentication).
+pub fn security_from_network(
+    auth: network::Authentication,
+    cipher: network::Encryption
+) -> Result<(Authentication, Encryption), UnsupportedSecurity> {
+    let profile_auth = Authentication::from_network_auth(auth.clone())?;
+    let profile_cipher = Encryption::from_network_cipher(cipher.clone())?;
+
+    let is_valid = match (&profile_auth, &profile_cipher) {
+        (Authentication::Open, Encryption::None | Encryption::WEP) => true,
+        (Authentication::Shared, Encryption::WEP) => true,
+        (
+            Authentication::WPA | Authentication::WPAPSK
+            | Authentication::WPA2 | Authentication::WPA2PSK,
+            Encryption::TKIP | Encryption::AES
+        ) => true,
+        (
+            Authentication::WPA3SAE | Authentication::OWE | Authentication::WPA3ENT,
+            Encryption::AES | Encryption::GCMP | Encryption::GCMP256
+        ) => true,
+        (Authentication::WPA3ENT192 | Authentication::WPA3, Encryption::GCMP256) => true,
+        _ => false
+    };
+
+    if is_valid {
+        Ok((profile_auth, profile_cipher))
+    } else {
+        Err(UnsupportedSecurity::Combination(auth, cipher))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumString, Display)]
 pub enum KeyType {
     #[strum(to_string = "networkKey")]
     NetworkKey,
@@ -88,11 +147,11 @@ impl ConnectionType {
         }
     }
 
-    pub fn from_bss(bss: Bss) -> Self {
+    pub fn from_bss(bss: Bss) -> Result<Self, UnsupportedSecurity> {
         match bss {
-            Bss::Infrastructure => Self::ESS,
-            Bss::Independent => Self::IBSS,
-            _ => unimplemented!()
+            Bss::Infrastructure => Ok(Self::ESS),
+            Bss::Independent => Ok(Self::IBSS),
+            Bss::Any => Err(UnsupportedSecurity::Bss(bss)),
         }
     }
 }
@@ -128,7 +187,7 @@ impl Default for MacRandomization {
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SharedKey {
     #[serde(rename = "keyType")]
     #[serde_as(as = "DisplayFromStr")]
@@ -137,6 +196,15 @@ pub struct SharedKey {
     #[serde(rename = "keyMaterial")]
     pub key_material: String,
 }
+impl std::fmt::Debug for SharedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedKey")
+            .field("key_type", &self.key_type)
+            .field("protected", &self.protected)
+            .field("key_material", &super::Profile::REDACTED)
+            .finish()
+    }
+}
 
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -165,7 +233,10 @@ pub struct Security {
     pub auth_encryption: AuthEncryption,
     #[serde(rename = "sharedKey")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub shared_key: Option<SharedKey>
+    pub shared_key: Option<SharedKey>,
+    #[serde(rename = "OneX")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub one_x: Option<super::onex::OneX>,
 }
 
 #[serde_as]
@@ -193,12 +264,19 @@ impl SSID {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SSIDConfig {
     #[serde(rename = "SSID")]
-    pub ssid: SSID
+    pub ssid: SSID,
+    /// ## Whether the network hides its SSID
+    /// Makes Windows send directed probe requests
+    /// instead of waiting for beacons.
+    #[serde(rename = "nonBroadcast")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_broadcast: Option<bool>,
 }
 impl SSIDConfig {
     pub fn from_string(string: String) -> Self {
         Self {
-            ssid: SSID::from_string(string)
+            ssid: SSID::from_string(string),
+            non_broadcast: None
         }
     }
 }
@@ -223,6 +301,7 @@ pub struct WLANProfile {
     #[serde(rename = "MSM")]
     pub msm: MSM,
     #[serde(rename = "MacRandomization")]
+    #[serde(default)]
     pub mac_randomization: MacRandomization,
 }
 // --------- Serialization ---------
@@ -272,7 +351,8 @@ impl WLANProfile {
                 ssid: SSID {
                     hex: hex::encode_upper(&friendly.ssid),
                     name: friendly.ssid
-                }
+                },
+                non_broadcast: if friendly.non_broadcast { Some(true) } else { None }
             },
             connection_type: friendly.connection.kind,
             connection_mode: friendly.connection.mode,
@@ -282,7 +362,7 @@ impl WLANProfile {
                     auth_encryption: AuthEncryption {
                         authentication: friendly.security.auth,
                         encryption: friendly.security.cipher,
-                        use_one_x: false
+                        use_one_x: friendly.security.enterprise.is_some()
                     },
                     shared_key: friendly.security.key.map(
                         |key| SharedKey {
@@ -290,6 +370,9 @@ impl WLANProfile {
                             protected: key.is_encrypted,
                             key_material: key.content
                         }
+                    ),
+                    one_x: friendly.security.enterprise.as_ref().map(
+                        |enterprise| super::onex::OneX::from_enterprise(enterprise)
                     )
                 }
             },
@@ -299,4 +382,92 @@ impl WLANProfile {
             },
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use network::{Authentication as Auth, Encryption as Cipher};
+
+    const AUTHS: [Auth; 13] = [
+        Auth::Open, Auth::SharedKey, Auth::Wpa, Auth::WpaPsk, Auth::WpaNone,
+        Auth::Rsna, Auth::RsnaPsk, Auth::Wpa3, Auth::Wpa3Sae, Auth::Owe,
+        Auth::Wpa3Ent, Auth::IhvStart, Auth::IhvEnd,
+    ];
+    const CIPHERS: [Cipher; 17] = [
+        Cipher::None, Cipher::Wep40, Cipher::Tkip, Cipher::Ccmp, Cipher::Wep104,
+        Cipher::Bip, Cipher::Gcmp, Cipher::Gcmp256, Cipher::Ccmp256, Cipher::BipGmac128,
+        Cipher::BipGmac256, Cipher::BipCmac256, Cipher::WpaUseGroup, Cipher::RsnUseGroup,
+        Cipher::Wep, Cipher::IhvStart, Cipher::IhvEnd,
+    ];
+
+    /// ## The profile pair for a scanned pair, `None` if it has none
+    fn expected(auth: &Auth, cipher: &Cipher) -> Option<(&'static str, &'static str)> {
+        let wep = matches!(cipher, Cipher::Wep | Cipher::Wep40 | Cipher::Wep104);
+        let profile_cipher = match cipher {
+            Cipher::None => "none",
+            Cipher::Wep | Cipher::Wep40 | Cipher::Wep104 => "WEP",
+            Cipher::Tkip => "TKIP",
+            Cipher::Ccmp => "AES",
+            Cipher::Gcmp => "GCMP",
+            Cipher::Gcmp256 => "GCMP256",
+            _ => return None
+        };
+
+        let profile_auth = match auth {
+            Auth::Open if wep || matches!(cipher, Cipher::None) => "open",
+            Auth::SharedKey if wep => "shared",
+            Auth::Wpa if matches!(cipher, Cipher::Tkip | Cipher::Ccmp) => "WPA",
+            Auth::WpaPsk if matches!(cipher, Cipher::Tkip | Cipher::Ccmp) => "WPAPSK",
+            Auth::Rsna if matches!(cipher, Cipher::Tkip | Cipher::Ccmp) => "WPA2",
+            Auth::RsnaPsk if matches!(cipher, Cipher::Tkip | Cipher::Ccmp) => "WPA2PSK",
+            Auth::Wpa3Sae if matches!(cipher, Cipher::Ccmp | Cipher::Gcmp | Cipher::Gcmp256) => "WPA3SAE",
+            Auth::Owe if matches!(cipher, Cipher::Ccmp | Cipher::Gcmp | Cipher::Gcmp256) => "OWE",
+            Auth::Wpa3Ent if matches!(cipher, Cipher::Ccmp | Cipher::Gcmp | Cipher::Gcmp256) => "WPA3ENT",
+            Auth::Wpa3 if matches!(cipher, Cipher::Gcmp256) => "WPA3ENT192",
+            _ => return None
+        };
+
+        Some((profile_auth, profile_cipher))
+    }
+
+    #[test]
+    fn every_scanned_pair_is_mapped_or_rejected() {
+        let mut supported = 0;
+
+        for auth in AUTHS.iter() {
+            for cipher in CIPHERS.iter() {
+                let result = security_from_network(auth.clone(), cipher.clone())
+                    .map(|(auth, cipher)| (auth.to_string(), cipher.to_string()));
+                let expected = expected(auth, cipher)
+                    .map(|(auth, cipher)| (auth.to_string(), cipher.to_string()));
+
+                assert_eq!(result.as_ref().ok(), expected.as_ref(), "{:?} + {:?}: {:?}", auth, cipher, result);
+                supported += expected.is_some() as usize;
+            }
+        }
+
+        assert_eq!(supported, 4 + 3 + 4 * 2 + 3 * 3 + 1);
+    }
+
+    #[test]
+    fn wep_keys_are_network_keys() {
+        let network = |auth, cipher| network::Network {
+            ssid: "Legacy".to_string(),
+            profile: None,
+            bss: Bss::Infrastructure,
+            bssids: 1,
+            connectable: true,
+            unconnectable_reason: None,
+            phys: vec![],
+            signal_quality: 100,
+            security: network::Security { enabled: true, auth, cipher },
+        };
+        let key = Some(super::super::Key::from_plain("0123456789"));
+
+        let wep = super::super::Profile::from_network(network(Auth::Open, Cipher::Wep40), key.clone()).unwrap();
+        assert!(matches!(wep.security.key.unwrap().kind, KeyType::NetworkKey));
+
+        let wpa2 = super::super::Profile::from_network(network(Auth::RsnaPsk, Cipher::Ccmp), key).unwrap();
+        assert!(matches!(wpa2.security.key.unwrap().kind, KeyType::PassPhrase));
+    }
+}
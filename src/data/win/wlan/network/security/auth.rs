@@ -40,4 +40,9 @@ impl Authentication {
             }
         }
     }
+
+    /// ## Whether it needs 802.1X (EAP) credentials
+    pub fn is_enterprise(&self) -> bool {
+        matches!(self, Self::Wpa | Self::Rsna | Self::Wpa3 | Self::Wpa3Ent)
+    }
 }
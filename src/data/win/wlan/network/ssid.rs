@@ -0,0 +1,20 @@
+use windows::Win32::NetworkManagement::WiFi;
+
+
+pub fn from_dot11_ssid(ssid: &WiFi::DOT11_SSID) -> String {
+    let len = (ssid.uSSIDLength as usize).min(ssid.ucSSID.len());
+    String::from_utf8_lossy(&ssid.ucSSID[..len]).to_string()
+}
+
+pub fn to_dot11_ssid(ssid: &str) -> WiFi::DOT11_SSID {
+    let bytes = ssid.as_bytes();
+    let len = bytes.len().min(WiFi::DOT11_SSID_MAX_LENGTH as usize);
+
+    let mut raw = WiFi::DOT11_SSID {
+        uSSIDLength: len as u32,
+        ucSSID: [0; 32],
+    };
+    raw.ucSSID[..len].copy_from_slice(&bytes[..len]);
+
+    raw
+}
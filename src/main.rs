@@ -7,15 +7,16 @@ use app::wlan::interface;
 use app::wlan::network;
 
 use std::path::PathBuf;
+use clap::Parser;
 use std::sync::Arc;
 use lazy_static::lazy_static;
 use once_cell::sync::OnceCell;
-use log::{debug, info, error};
+use log::{debug, info, warn, error};
 
 
 lazy_static! {
-    pub static ref DATA_PATH: PathBuf = PathBuf::from_iter([".", "wifu-data"]);
-    pub static ref CFG_PATH: PathBuf = DATA_PATH.join("cfg.json");
+    pub static ref DATA_PATH: PathBuf = app::cli::options().data_dir();
+    pub static ref CFG_PATH: PathBuf = app::cli::options().config_path(&DATA_PATH);
     pub static ref CHANNEL: (tokio::sync::mpsc::Sender<()>, tokio::sync::RwLock<tokio::sync::mpsc::Receiver<()>>) = {
         let (tx, rx) = tokio::sync::mpsc::channel::<()>(100);
         (tx, tokio::sync::RwLock::new(rx))
@@ -23,15 +24,39 @@ lazy_static! {
 }
 
 static LOGGER: Logger = Logger;
-pub static CONFIG: OnceCell<data::app::cfg::Config> = OnceCell::new();
+pub static CONFIG: OnceCell<std::sync::RwLock<Arc<data::app::cfg::Config>>> = OnceCell::new();
 pub static WLAN: OnceCell<Arc<win::Wlan>> = OnceCell::new();
 
 
+/// ## Current config
+/// 
+/// It's swapped when the file changes,
+/// so don't hold onto it for long.
+pub fn config() -> Arc<data::app::cfg::Config> {
+    CONFIG.get().unwrap().read().unwrap().clone()
+}
+
+
 #[tokio::main]
 async fn main() {
-    Logger::init().unwrap();
+    let cli = app::cli::Cli::parse();
+    app::cli::OPTIONS.set(cli.options).unwrap();
+    let options = app::cli::options();
+    Logger::init(options.log_level.map(|level| level.to_filter()).unwrap_or(Logger::DEFAULT_LEVEL)).unwrap();
+
+    if let Some(command) = cli.command {
+        if command.needs_config() && !app::load_config().await {
+            std::process::exit(1);
+        }
+        if command.needs_wlan() {
+            WLAN.set(Arc::new(options.open_wlan().unwrap())).unwrap();
+        }
+        let ok = app::cli::run(command).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     let just_created_cfg = app::init_fs().await;
-    let config = CONFIG.get().unwrap();
+    let config = config();
 
     if just_created_cfg {
         info!("o The app was initialized and a config file was created here: {}", CFG_PATH.as_path().display());
@@ -40,15 +65,32 @@ async fn main() {
         return;
     }
 
-    if let Err(reasons) = config.is_valid() {
-        error!("x CONFIG is invalid due to the following reasons: {:?}", reasons);
+    if let Err(issues) = config.is_valid() {
+        error!("x CONFIG is invalid due to the following reasons:");
+        app::cfg::validation::log(&issues);
         return;
     }
 
-    WLAN.set(Arc::new(win::Wlan::new(win::wlan::ClientVersion::Second).unwrap())).unwrap();
+    WLAN.set(Arc::new(options.open_wlan().unwrap())).unwrap();
+    app::history::open();
+    app::webhooks::spawn_event_loop().await;
+    app::hooks::spawn_event_loop().await;
+    if options.dry_run {
+        warn!("! DRY RUN: nothing will be connected, disconnected or stored, watch for \"would\" in the log");
+    }
 
     interface::start().await;
     network::start().await;
+
+    if config.reload.enabled {
+        app::reload::spawn_event_loop().await;
+    }
+    if config.api.enabled {
+        app::api::spawn_event_loop().await;
+    }
+    if config.metrics.enabled {
+        app::metrics::spawn_event_loop().await;
+    }
     
     if !interface::CHOSEN.write().await.is_chosen() {
         debug!("main calls dead because no interface");